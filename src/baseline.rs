@@ -0,0 +1,241 @@
+//! Accepted findings for CI, written by `baseline create` and applied with `--baseline`.
+//!
+//! Each finding is identified by a fingerprint over its server, entity, the
+//! entity's own fingerprint, detector, location and code. The message is only
+//! used for findings without a code, from detectors that always word the same
+//! issue the same way; model or server prose and evidence are left out, so the
+//! same issue keeps its fingerprint across runs. Findings in the baseline are moved to
+//! `suppressed` and no longer fail the run; the report lists the findings that
+//! are new and the baseline entries that no longer match anything.
+
+use std::collections::HashSet;
+use std::fs;
+
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::mcp_types::{Finding, Severity};
+use crate::report::{EntityReport, ScanReport};
+
+pub const BASELINE_VERSION: u32 = 2;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaselineEntry {
+    pub fingerprint: String,
+    pub server: String,
+    /// `type:name`; missing for findings about the server itself.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity: Option<String>,
+    pub detector: String,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl BaselineEntry {
+    fn new(server: &str, entity: Option<&EntityReport>, finding: &Finding) -> Self {
+        Self {
+            fingerprint: fingerprint(server, entity, finding),
+            server: server.to_string(),
+            entity: entity.map(entity_id),
+            detector: finding.detector.clone(),
+            severity: finding.severity,
+            message: finding.message.clone(),
+        }
+    }
+}
+
+fn entity_id(entity: &EntityReport) -> String {
+    format!("{}:{}", entity.r#type, entity.name)
+}
+
+pub fn fingerprint(server: &str, entity: Option<&EntityReport>, finding: &Finding) -> String {
+    let id = entity.map(entity_id).unwrap_or_default();
+    let data = [
+        server,
+        &id,
+        entity.map(|e| e.fingerprint.as_str()).unwrap_or_default(),
+        &finding.detector,
+        finding.location.as_deref().unwrap_or_default(),
+        finding.code.as_deref().unwrap_or(&finding.message),
+    ]
+    .join("\0");
+    format!("{:x}", md5::compute(data.as_bytes()))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Baseline {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub entries: Vec<BaselineEntry>,
+}
+
+/// How a scan compares to the baseline.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct BaselineDiff {
+    pub suppressed: usize,
+    /// Findings not in the baseline.
+    pub new: Vec<BaselineEntry>,
+    /// Baseline entries no finding matched, on servers that were scanned.
+    pub resolved: Vec<BaselineEntry>,
+}
+
+/// Every finding of `report` with its server and entity.
+fn entries(report: &ScanReport) -> Vec<BaselineEntry> {
+    let mut entries = Vec::new();
+    for server in &report.servers {
+        entries.extend(server.findings.iter().map(|f| BaselineEntry::new(&server.server, None, f)));
+        for entity in &server.entities {
+            entries.extend(entity.findings.iter().map(|f| BaselineEntry::new(&server.server, Some(entity), f)));
+        }
+    }
+    entries
+}
+
+impl Baseline {
+    pub fn from_report(report: &ScanReport) -> Self {
+        let mut entries = entries(report);
+        entries.sort_by(|a, b| (&a.server, &a.entity, &a.fingerprint).cmp(&(&b.server, &b.entity, &b.fingerprint)));
+        entries.dedup_by(|a, b| a.fingerprint == b.fingerprint);
+        Self { version: BASELINE_VERSION, created_at: Utc::now(), entries }
+    }
+
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let path = shellexpand::tilde(path).into_owned();
+        let data = fs::read_to_string(&path).with_context(|| format!("could not read baseline {}", path))?;
+        let baseline: Self = serde_json::from_str(&data).with_context(|| format!("invalid baseline {}", path))?;
+        if baseline.version != BASELINE_VERSION {
+            anyhow::bail!(
+                "unsupported baseline version {} in {}, recreate it with `baseline create`",
+                baseline.version,
+                path
+            );
+        }
+        Ok(baseline)
+    }
+
+    pub fn write(&self, path: &str) -> anyhow::Result<()> {
+        let path = shellexpand::tilde(path).into_owned();
+        fs::write(&path, serde_json::to_string_pretty(self)?).with_context(|| format!("could not write baseline {}", path))
+    }
+
+    /// Move the findings of `report` that are in the baseline to `suppressed`
+    /// and record the difference in `report.baseline`.
+    pub fn apply(&self, report: &mut ScanReport) {
+        let known: HashSet<&str> = self.entries.iter().map(|e| e.fingerprint.as_str()).collect();
+        let mut matched = HashSet::new();
+        let mut diff = BaselineDiff::default();
+
+        let mut split = |server: &str, entity: Option<&EntityReport>, findings: Vec<Finding>| {
+            let (mut kept, mut accepted) = (Vec::new(), Vec::new());
+            for finding in findings {
+                let entry = BaselineEntry::new(server, entity, &finding);
+                if known.contains(entry.fingerprint.as_str()) {
+                    matched.insert(entry.fingerprint);
+                    accepted.push(finding);
+                } else {
+                    diff.new.push(entry);
+                    kept.push(finding);
+                }
+            }
+            (kept, accepted)
+        };
+        for server in &mut report.servers {
+            let (kept, accepted) = split(&server.server, None, std::mem::take(&mut server.findings));
+            server.findings = kept;
+            server.suppressed.extend(accepted);
+            for entity in &mut server.entities {
+                let findings = std::mem::take(&mut entity.findings);
+                let (kept, accepted) = split(&server.server, Some(entity), findings);
+                entity.findings = kept;
+                entity.suppressed.extend(accepted);
+            }
+        }
+
+        let scanned: HashSet<&str> = report
+            .servers
+            .iter()
+            .filter(|s| s.error.is_none())
+            .map(|s| s.server.as_str())
+            .collect();
+        diff.suppressed = report
+            .servers
+            .iter()
+            .map(|s| s.suppressed.len() + s.entities.iter().map(|e| e.suppressed.len()).sum::<usize>())
+            .sum();
+        diff.resolved = self
+            .entries
+            .iter()
+            .filter(|e| scanned.contains(e.server.as_str()) && !matched.contains(&e.fingerprint))
+            .cloned()
+            .collect();
+        report.baseline = Some(diff);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp_types::{Entity, Server};
+    use crate::report::{EntityReport, ServerReport};
+
+    fn report(messages: &[&str]) -> ScanReport {
+        report_with(messages.iter().map(|m| Finding::new("schema", Severity::High, *m)).collect())
+    }
+
+    fn report_with(findings: Vec<Finding>) -> ScanReport {
+        let server: Server = serde_json::from_value(serde_json::json!({ "command": "npx", "args": ["-y", "math"] })).unwrap();
+        let tool = Entity::Tool(
+            serde_json::from_value(serde_json::json!({
+                "name": "add",
+                "description": "Adds two numbers",
+                "inputSchema": { "type": "object" },
+            }))
+            .unwrap(),
+        );
+        let mut entity = EntityReport::new(&tool);
+        entity.verified = Some(false);
+        entity.findings = findings
+            .into_iter()
+            .map(|f| f.with_evidence(format!("run {}", Utc::now())))
+            .collect();
+        let mut server_report = ServerReport::new("mcp.json", "math", &server);
+        server_report.add_entities(&[tool], vec![entity]);
+        ScanReport { servers: vec![server_report], ..Default::default() }
+    }
+
+    #[test]
+    fn test_baseline_suppresses_known_findings() {
+        let baseline = Baseline::from_report(&report(&["old issue", "fixed issue"]));
+        assert_eq!(baseline.entries.len(), 2);
+
+        let mut known = report(&["old issue"]);
+        baseline.apply(&mut known);
+        let diff = known.baseline.clone().unwrap();
+        assert_eq!(diff.suppressed, 1);
+        assert!(diff.new.is_empty());
+        assert_eq!(diff.resolved[0].message, "fixed issue");
+        assert_eq!(known.exit_code(), 0);
+
+        let mut regressed = report(&["old issue", "new issue"]);
+        baseline.apply(&mut regressed);
+        let diff = regressed.baseline.clone().unwrap();
+        assert_eq!(diff.new.len(), 1);
+        assert_eq!(diff.new[0].entity.as_deref(), Some("tool:add"));
+        assert_eq!(regressed.exit_code(), 1);
+    }
+
+    #[test]
+    fn test_model_prose_is_not_fingerprinted() {
+        let judged = |rationale: &str| {
+            report_with(vec![
+                Finding::new("llm", Severity::High, format!("malicious: {}", rationale)).with_code("malicious"),
+            ])
+        };
+        let baseline = Baseline::from_report(&judged("asks for the SSH key"));
+
+        let mut reworded = judged("wants the private key in ~/.ssh");
+        baseline.apply(&mut reworded);
+        assert!(reworded.baseline.unwrap().new.is_empty());
+    }
+}
@@ -0,0 +1,234 @@
+//! Signed whitelist and policy bundles, shared by a team.
+//!
+//! A bundle carries whitelist entries and, optionally, the text of a policy
+//! file. It is signed with an ed25519 key and only honored when the key is
+//! listed under `[bundles] trusted_signers` in `config.toml`:
+//!
+//! ```toml
+//! [bundles]
+//! trusted_signers = [{ name = "security-team", key = "<base64 public key>" }]
+//! paths = ["./.mcp-scan/team.bundle.json"]
+//! ```
+//!
+//! The signature covers the exact `payload` string, so the bundle can be
+//! committed and copied around without any canonical JSON form.
+
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{DateTime, Utc};
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::config::TrustedSigner;
+use crate::whitelist::WhitelistEntry;
+
+pub const BUNDLE_FORMAT: &str = "mcp-scan-bundle/1";
+/// Bundles imported with `bundle import`, relative to the storage directory.
+pub const BUNDLES_DIR: &str = "bundles";
+
+/// What a bundle approves.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BundleContents {
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub whitelist: Vec<WhitelistEntry>,
+    /// Policy file text, see `policy.rs`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy: Option<String>,
+}
+
+/// A bundle as stored on disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedBundle {
+    pub format: String,
+    /// `BundleContents` as JSON; the signed bytes.
+    pub payload: String,
+    /// Base64 ed25519 public key of the signer.
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Base64 ed25519 signature over `payload`.
+    #[serde(default)]
+    pub signature: Option<String>,
+}
+
+/// A bundle whose signature checked out against a trusted signer.
+#[derive(Debug, Clone)]
+pub struct Bundle {
+    pub path: PathBuf,
+    pub signer: String,
+    pub contents: BundleContents,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BundleError {
+    Invalid(String),
+    Unsigned,
+    UntrustedSigner(String),
+    BadSignature,
+}
+
+impl fmt::Display for BundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BundleError::Invalid(message) => write!(f, "invalid bundle: {}", message),
+            BundleError::Unsigned => f.write_str("bundle is not signed"),
+            BundleError::UntrustedSigner(key) => write!(f, "bundle is signed by an untrusted key {}", key),
+            BundleError::BadSignature => f.write_str("bundle signature does not match its contents"),
+        }
+    }
+}
+
+impl std::error::Error for BundleError {}
+
+fn decode_key(key: &str) -> Option<VerifyingKey> {
+    let bytes: [u8; 32] = BASE64.decode(key.trim()).ok()?.try_into().ok()?;
+    VerifyingKey::from_bytes(&bytes).ok()
+}
+
+pub fn encode_public_key(key: &SigningKey) -> String {
+    BASE64.encode(key.verifying_key().as_bytes())
+}
+
+/// A new random signing key.
+pub fn generate_key() -> anyhow::Result<SigningKey> {
+    let mut secret = [0u8; 32];
+    getrandom::getrandom(&mut secret).map_err(|e| anyhow::anyhow!("could not generate a key: {}", e))?;
+    Ok(SigningKey::from_bytes(&secret))
+}
+
+/// Write `key` as base64, readable by the owner only.
+pub fn write_key(key: &SigningKey, path: &Path) -> anyhow::Result<()> {
+    if path.exists() {
+        anyhow::bail!("{:?} already exists", path);
+    }
+    fs::write(path, BASE64.encode(key.to_bytes())).with_context(|| format!("could not write {:?}", path))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+pub fn read_key(path: &Path) -> anyhow::Result<SigningKey> {
+    let data = fs::read_to_string(path).with_context(|| format!("could not read signing key {:?}", path))?;
+    let secret: [u8; 32] = BASE64
+        .decode(data.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .with_context(|| format!("{:?} is not a base64 ed25519 signing key", path))?;
+    Ok(SigningKey::from_bytes(&secret))
+}
+
+impl SignedBundle {
+    pub fn sign(contents: &BundleContents, key: &SigningKey) -> Self {
+        let payload = serde_json::to_string_pretty(contents).expect("bundle contents serialize");
+        let signature = key.sign(payload.as_bytes());
+        Self {
+            format: BUNDLE_FORMAT.to_string(),
+            payload,
+            key: Some(encode_public_key(key)),
+            signature: Some(BASE64.encode(signature.to_bytes())),
+        }
+    }
+
+    /// Check the signature against `trusted` and parse the contents.
+    /// Returns the name of the signer with the contents.
+    pub fn verify(&self, trusted: &[TrustedSigner]) -> Result<(String, BundleContents), BundleError> {
+        if self.format != BUNDLE_FORMAT {
+            return Err(BundleError::Invalid(format!("unsupported format {:?}", self.format)));
+        }
+        let (Some(key), Some(signature)) = (&self.key, &self.signature) else {
+            return Err(BundleError::Unsigned);
+        };
+        let verifying_key = decode_key(key).ok_or_else(|| BundleError::Invalid("malformed public key".to_string()))?;
+        let signer = trusted
+            .iter()
+            .find(|s| decode_key(&s.key) == Some(verifying_key))
+            .ok_or_else(|| BundleError::UntrustedSigner(key.clone()))?;
+        let signature: [u8; 64] = BASE64
+            .decode(signature)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| BundleError::Invalid("malformed signature".to_string()))?;
+        verifying_key
+            .verify(self.payload.as_bytes(), &Signature::from_bytes(&signature))
+            .map_err(|_| BundleError::BadSignature)?;
+        let contents: BundleContents =
+            serde_json::from_str(&self.payload).map_err(|e| BundleError::Invalid(e.to_string()))?;
+        if let Some(policy) = &contents.policy {
+            crate::policy::Policy::parse(policy).map_err(|e| BundleError::Invalid(format!("{:#}", e)))?;
+        }
+        Ok((signer.name.clone(), contents))
+    }
+}
+
+impl Bundle {
+    /// Read and verify the bundle at `path`.
+    pub fn load(path: &Path, trusted: &[TrustedSigner]) -> anyhow::Result<Self> {
+        let data = fs::read_to_string(path).with_context(|| format!("could not read bundle {:?}", path))?;
+        let signed: SignedBundle = serde_json::from_str(&data)
+            .map_err(|e| BundleError::Invalid(e.to_string()))
+            .with_context(|| format!("rejected bundle {:?}", path))?;
+        let (signer, contents) = signed.verify(trusted).with_context(|| format!("rejected bundle {:?}", path))?;
+        Ok(Self { path: path.to_path_buf(), signer, contents })
+    }
+}
+
+/// Bundles imported into the storage directory, followed by `paths`.
+/// Fails on the first bundle that does not verify.
+pub fn load_all(storage_path: &str, paths: &[String], trusted: &[TrustedSigner]) -> anyhow::Result<Vec<Bundle>> {
+    let dir = PathBuf::from(shellexpand::tilde(storage_path).into_owned()).join(BUNDLES_DIR);
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+        .map(|entries| entries.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    files.retain(|p| p.extension().is_some_and(|ext| ext == "json"));
+    files.sort();
+    files.extend(paths.iter().map(|p| PathBuf::from(shellexpand::tilde(p).into_owned())));
+    files.iter().map(|path| Bundle::load(path, trusted)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents() -> BundleContents {
+        BundleContents {
+            created_at: Utc::now(),
+            whitelist: Vec::new(),
+            policy: Some("require_pinned = true".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let key = generate_key().unwrap();
+        let trusted = [TrustedSigner { name: "security-team".to_string(), key: encode_public_key(&key) }];
+        let signed = SignedBundle::sign(&contents(), &key);
+
+        let (signer, verified) = signed.verify(&trusted).unwrap();
+        assert_eq!(signer, "security-team");
+        assert_eq!(verified.policy.as_deref(), Some("require_pinned = true"));
+
+        let mut tampered = signed.clone();
+        tampered.payload = tampered.payload.replace("true", "false");
+        assert_eq!(tampered.verify(&trusted).unwrap_err(), BundleError::BadSignature);
+
+        let mut unsigned = signed.clone();
+        unsigned.signature = None;
+        assert_eq!(unsigned.verify(&trusted).unwrap_err(), BundleError::Unsigned);
+
+        let other = SignedBundle::sign(&contents(), &generate_key().unwrap());
+        assert!(matches!(other.verify(&trusted), Err(BundleError::UntrustedSigner(_))));
+
+        let mut broken_policy = contents();
+        broken_policy.policy = Some("require_pinned = 1".to_string());
+        let signed = SignedBundle::sign(&broken_policy, &key);
+        assert!(matches!(signed.verify(&trusted), Err(BundleError::Invalid(_))));
+    }
+}
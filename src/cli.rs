@@ -1,5 +1,11 @@
 
-use clap::{Args, Parser, Subcommand};
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::config::LLMProvider;
+use crate::verifier::{Fusion, VerifierKind};
+use crate::verdict_cache::DEFAULT_CACHE_TTL_HOURS;
+use crate::whitelist::parse_expiry;
 
 
 #[derive(Parser)]
@@ -13,7 +19,7 @@ Examples:
   mcp-scan ~/custom/config.json # Scan a specific config file
   mcp-scan inspect             # Just inspect tools without verification
   mcp-scan whitelist           # View whitelisted tools
-  mcp-scan whitelist tool \"add\" \"a1b2c3...\" # Whitelist the 'add' tool")]
+  mcp-scan whitelist math tool \"add\" \"a1b2c3...\" --reason \"reviewed\" # Whitelist the 'add' tool of the 'math' server")]
 pub struct Cli {
     #[command(subcommand)]
     pub command: Option<Commands>,
@@ -27,6 +33,91 @@ pub enum Commands {
     Inspect(InspectArgs),
     /// Manage the whitelist of approved entities
     Whitelist(WhitelistArgs),
+    /// Run a local mock verification server for tests and offline demos
+    MockServer(MockServerArgs),
+    /// Create, sign and import shared whitelist and policy bundles
+    Bundle(BundleArgs),
+    /// Record the current findings so later scans fail only on new ones
+    Baseline(BaselineArgs),
+    /// Maintain the storage directory
+    Storage(StorageArgs),
+}
+
+#[derive(Args)]
+pub struct StorageArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    #[command(subcommand)]
+    pub command: StorageCommand,
+}
+
+#[derive(Subcommand)]
+pub enum StorageCommand {
+    /// Convert storage written by an older mcp-scan, keeping a backup of the original
+    Migrate,
+}
+
+#[derive(Args)]
+pub struct BaselineArgs {
+    #[command(subcommand)]
+    pub command: BaselineCommand,
+}
+
+#[derive(Subcommand)]
+pub enum BaselineCommand {
+    /// Scan and write every finding to a baseline file
+    Create {
+        /// Where to write the baseline
+        #[arg(long, default_value = "mcp-scan-baseline.json")]
+        out: String,
+
+        #[command(flatten)]
+        scan: ScanArgs,
+    },
+}
+
+#[derive(Args)]
+pub struct BundleArgs {
+    #[command(flatten)]
+    pub common: CommonArgs,
+
+    #[command(subcommand)]
+    pub command: BundleCommand,
+}
+
+#[derive(Subcommand)]
+pub enum BundleCommand {
+    /// Generate an ed25519 signing key and print its public key
+    Keygen {
+        /// Where to write the private key
+        #[arg(long)]
+        out: String,
+    },
+    /// Sign the local whitelist, and the policy file given with `--policy`, into a bundle
+    Export {
+        /// Private key written by `bundle keygen`
+        #[arg(long)]
+        key: String,
+        /// Where to write the bundle
+        #[arg(long)]
+        out: String,
+    },
+    /// Verify a bundle and copy it into the storage directory, so every scan honors it
+    Import {
+        path: String,
+    },
+    /// Verify a bundle and print what it approves
+    Verify {
+        path: String,
+    },
+}
+
+#[derive(Args)]
+pub struct MockServerArgs {
+    /// Address to listen on
+    #[arg(long, default_value = "127.0.0.1:8787")]
+    pub listen: String,
 }
 
 #[derive(Parser)]
@@ -46,6 +137,101 @@ pub struct CommonArgs {
     /// LLM API URL (can also be set via MCP_LLM_API_URL env var)
     #[arg(long, short = 'u',  env = "LLM_API_URL")]
     pub llm_api_url: Option<String>,
+
+    /// Path to a TOML config file [default: ~/.config/mcp-scan/config.toml]
+    #[arg(long, short = 'c', env = "MCP_SCAN_CONFIG")]
+    pub config: Option<String>,
+
+    /// LLM API protocol [default: openai]
+    #[arg(long, value_enum, env = "LLM_PROVIDER")]
+    pub llm_provider: Option<LLMProvider>,
+
+    /// LLM model name
+    #[arg(long, env = "LLM_MODEL")]
+    pub llm_model: Option<String>,
+
+    /// LLM sampling temperature
+    #[arg(long, env = "LLM_TEMPERATURE")]
+    pub llm_temperature: Option<f32>,
+
+    /// Maximum number of tokens the LLM may generate per request
+    #[arg(long, env = "LLM_MAX_TOKENS")]
+    pub llm_max_tokens: Option<u32>,
+
+    /// Seconds to wait for an LLM response
+    #[arg(long, env = "LLM_TIMEOUT")]
+    pub llm_timeout: Option<u64>,
+
+    /// Approximate tokens of entity data per LLM or verification request [default: 6000]
+    #[arg(long, env = "LLM_TOKEN_BUDGET")]
+    pub llm_token_budget: Option<usize>,
+
+    /// File containing the system prompt for the LLM judge
+    #[arg(long, env = "LLM_JUDGE_PROMPT_FILE")]
+    pub llm_judge_prompt_file: Option<String>,
+
+    /// File containing the system prompt for translation
+    #[arg(long, env = "LLM_TRANSLATE_PROMPT_FILE")]
+    pub llm_translate_prompt_file: Option<String>,
+
+    /// Retries for failed LLM and verification requests [default: 3]
+    #[arg(long, env = "MCP_SCAN_HTTP_RETRIES")]
+    pub http_retries: Option<u32>,
+
+    /// Concurrent requests per endpoint [default: 4]
+    #[arg(long, env = "MCP_SCAN_HTTP_CONCURRENCY")]
+    pub http_concurrency: Option<usize>,
+
+    /// Maximum requests per second per endpoint
+    #[arg(long, env = "MCP_SCAN_HTTP_RATE_LIMIT")]
+    pub http_rate_limit: Option<f64>,
+
+    /// Most list requests sent to one server [default: 100]
+    #[arg(long, env = "MCP_SCAN_MAX_PAGES")]
+    pub max_pages: Option<usize>,
+
+    /// Most entities accepted from one server [default: 1000]
+    #[arg(long, env = "MCP_SCAN_MAX_ENTITIES")]
+    pub max_entities: Option<usize>,
+
+    /// Longer descriptions are truncated [default: 65536]
+    #[arg(long, env = "MCP_SCAN_MAX_DESCRIPTION_BYTES")]
+    pub max_description_bytes: Option<usize>,
+
+    /// Most bytes of entity data accepted from one server [default: 16777216]
+    #[arg(long, env = "MCP_SCAN_MAX_SERVER_BYTES")]
+    pub max_server_bytes: Option<usize>,
+
+    /// Verifiers to run, comma separated [default: remote,heuristics; heuristics without --base-url]
+    #[arg(long, value_delimiter = ',', env = "MCP_SCAN_VERIFIERS")]
+    pub verifiers: Option<Vec<VerifierKind>>,
+
+    /// How the verdicts of several verifiers are combined [default: any]
+    #[arg(long, env = "MCP_SCAN_FUSION")]
+    pub fusion: Option<Fusion>,
+
+    /// Weighted share of flagging verifiers at which an entity is flagged, in (0, 1] [default: 0.5]
+    #[arg(long, env = "MCP_SCAN_FUSION_THRESHOLD")]
+    pub fusion_threshold: Option<f64>,
+
+    /// Vote weight of a verifier for weighted fusion, e.g. `remote=2`; can be repeated
+    #[arg(long, value_name = "VERIFIER=WEIGHT", value_parser = parse_weight)]
+    pub verifier_weight: Vec<(VerifierKind, f64)>,
+
+    /// Organisation policy file (TOML); enables the policy verifier
+    #[arg(long, env = "MCP_SCAN_POLICY")]
+    pub policy: Option<String>,
+
+    /// Signed whitelist and policy bundle to honor; can be repeated
+    #[arg(long, env = "MCP_SCAN_BUNDLES", value_delimiter = ',')]
+    pub bundle: Vec<String>,
+}
+
+fn parse_weight(value: &str) -> Result<(VerifierKind, f64), String> {
+    let (kind, weight) = value.split_once('=').ok_or("expected VERIFIER=WEIGHT")?;
+    let kind = VerifierKind::from_str(kind.trim(), true)?;
+    let weight = weight.trim().parse::<f64>().map_err(|e| e.to_string())?;
+    Ok((kind, weight))
 }
 
 #[derive(Args)]
@@ -53,7 +239,7 @@ pub struct ScanArgs {
     #[command(flatten)]
     pub common: CommonArgs,
     
-    /// Seconds to wait for server connections
+    /// Seconds to wait for server connections and for each verification API request
     #[arg(long, default_value = "10")]
     pub server_timeout: i64,
     
@@ -64,6 +250,35 @@ pub struct ScanArgs {
     /// Number of checks per server
     #[arg(long, default_value = "1")]
     pub checks_per_server: i64,
+
+    /// Review each entity with the LLM judge in addition to the verification server
+    #[arg(long)]
+    pub llm_judge: bool,
+
+    /// Have the LLM translate descriptions written in several languages into LANGUAGE
+    /// and flag those whose parts disagree
+    #[arg(long, value_name = "LANGUAGE")]
+    pub cross_language: Option<String>,
+
+    /// Write the scan report as JSON to this file
+    #[arg(long)]
+    pub json: Option<String>,
+
+    /// Hours a cached verification or LLM verdict stays valid
+    #[arg(long, default_value_t = DEFAULT_CACHE_TTL_HOURS)]
+    pub cache_ttl: i64,
+
+    /// Do not read or write the verdict cache
+    #[arg(long)]
+    pub no_cache: bool,
+
+    /// Re-evaluate every entity and overwrite its cached verdicts
+    #[arg(long, conflicts_with = "no_cache")]
+    pub refresh_cache: bool,
+
+    /// Accept the findings in this baseline file, failing only on new ones
+    #[arg(long)]
+    pub baseline: Option<String>,
     pub files: Vec<String>,
 }
 
@@ -75,7 +290,14 @@ pub struct InspectArgs {
     /// Seconds to wait for server connections
     #[arg(long, default_value = "10")]
     pub server_timeout: i64,
-    pub languages: Option<String>,
+
+    /// Translate names and descriptions into these languages, e.g. `--languages English,中文`
+    #[arg(long, value_delimiter = ',')]
+    pub languages: Vec<String>,
+
+    /// Write the inspected entities and their translations as JSON to this file
+    #[arg(long)]
+    pub json: Option<String>,
     pub files: Vec<String>,
 }
 
@@ -90,14 +312,29 @@ pub struct WhitelistArgs {
     
     /// Only update local whitelist
     #[arg(long)]
-    local_only: bool,
-    
+    pub local_only: bool,
+
+    /// Server the entity belongs to, as named in the client config
+    pub server: Option<String>,
+
     /// Type of entity to whitelist
-    entity_type: Option<String>,
-    
+    pub entity_type: Option<String>,
+
     /// Name of the entity
-    entity_name: Option<String>,
-    
-    /// Hash of the entity
-    entity_hash: Option<String>,
+    pub entity_name: Option<String>,
+
+    /// Fingerprint of the entity, as printed by `scan`
+    pub entity_hash: Option<String>,
+
+    /// Why the entity is approved; required when adding an entry
+    #[arg(long)]
+    pub reason: Option<String>,
+
+    /// Who approves the entity [default: $USER]
+    #[arg(long, env = "MCP_SCAN_APPROVER")]
+    pub approved_by: Option<String>,
+
+    /// Last day the approval applies (YYYY-MM-DD)
+    #[arg(long, value_parser = parse_expiry)]
+    pub expires: Option<DateTime<Utc>>,
 }
\ No newline at end of file
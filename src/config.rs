@@ -0,0 +1,573 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Context;
+use clap::ValueEnum;
+use directories::ProjectDirs;
+use serde::Deserialize;
+
+use crate::bundle;
+use crate::cli::CommonArgs;
+use crate::llm::client::{
+    ANTHROPIC_API_URL, AnthropicClient, ChatClient, OLLAMA_API_URL, OllamaClient, OpenAIClient,
+};
+use crate::llm::judge::JUDGE_SYSTEM_PROMPT;
+use crate::policy::Policy;
+use crate::verifier::{Fusion, VerifierKind};
+use crate::whitelist::WhitelistEntry;
+
+pub const DEFAULT_LLM_API_URL: &str = "https://api.openai.com/v1/chat/completions";
+pub const DEFAULT_LLM_MODEL: &str = "Qwen/Qwen3-8B";
+pub const DEFAULT_LLM_TEMPERATURE: f32 = 0.7;
+pub const DEFAULT_LLM_TIMEOUT_SECS: u64 = 60;
+/// Approximate tokens of entity data per LLM or verification request.
+pub const DEFAULT_TOKEN_BUDGET: usize = 6000;
+pub const DEFAULT_HTTP_MAX_RETRIES: u32 = 3;
+pub const DEFAULT_HTTP_CONCURRENCY: usize = 4;
+pub const DEFAULT_MAX_PAGES: usize = 100;
+pub const DEFAULT_MAX_ENTITIES: usize = 1000;
+pub const DEFAULT_MAX_DESCRIPTION_BYTES: usize = 64 * 1024;
+pub const DEFAULT_MAX_SERVER_BYTES: usize = 16 * 1024 * 1024;
+/// Weighted share of flagging verifiers at which an entity counts as flagged.
+pub const DEFAULT_FUSION_THRESHOLD: f64 = 0.5;
+/// `{language}` is replaced with the target language of `inspect --languages`.
+pub const DEFAULT_TRANSLATE_PROMPT: &str = "/no_think 你是一个Json 数据翻译助手，将json数据中 name 和 description 的value翻译成{language},注意，1、不要翻译json的key,只翻译value。2、id 和 type 字段原样保留。 /no_think";
+
+/// Contents of the optional `config.toml`.
+///
+/// ```toml
+/// [llm]
+/// provider = "openai"
+/// api_url = "http://localhost:11434/v1/chat/completions"
+/// model = "qwen3:8b"
+/// temperature = 0.0
+/// max_tokens = 1024
+/// timeout = 120
+/// token_budget = 4000
+/// judge_prompt_file = "~/prompts/judge.txt"
+///
+/// [http]
+/// max_retries = 5
+/// concurrency = 2
+/// requests_per_second = 1.5
+///
+/// [limits]
+/// max_entities = 5000
+///
+/// [verification]
+/// verifiers = ["remote", "heuristics", "llm"]
+/// fusion = "weighted"
+/// threshold = 0.5
+/// weights = { remote = 2.0, llm = 1.0 }
+///
+/// [bundles]
+/// trusted_signers = [{ name = "security-team", key = "<base64 public key>" }]
+/// paths = ["./.mcp-scan/team.bundle.json"]
+/// ```
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub llm: LLMConfig,
+    pub http: HttpConfig,
+    pub limits: LimitsConfig,
+    pub verification: VerificationConfig,
+    pub bundles: BundlesConfig,
+}
+
+/// Which wire protocol the LLM endpoint speaks.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LLMProvider {
+    /// OpenAI-compatible chat completions (OpenAI, vLLM, LM Studio, SiliconFlow, ...)
+    #[default]
+    #[value(name = "openai")]
+    OpenAI,
+    /// Anthropic Messages API
+    Anthropic,
+    /// Ollama's native /api/chat
+    Ollama,
+}
+
+impl LLMProvider {
+    pub fn default_api_url(&self) -> &'static str {
+        match self {
+            LLMProvider::OpenAI => DEFAULT_LLM_API_URL,
+            LLMProvider::Anthropic => ANTHROPIC_API_URL,
+            LLMProvider::Ollama => OLLAMA_API_URL,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LLMConfig {
+    pub provider: Option<LLMProvider>,
+    pub api_key: Option<String>,
+    pub api_url: Option<String>,
+    pub model: Option<String>,
+    pub temperature: Option<f32>,
+    pub max_tokens: Option<u32>,
+    /// Request timeout in seconds.
+    pub timeout: Option<u64>,
+    /// Approximate tokens of entity data per request; larger inventories are split.
+    pub token_budget: Option<usize>,
+    pub judge_prompt: Option<String>,
+    pub judge_prompt_file: Option<String>,
+    pub translate_prompt: Option<String>,
+    pub translate_prompt_file: Option<String>,
+}
+
+/// Retry and rate limit settings for the LLM and verification endpoints.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HttpConfig {
+    pub max_retries: Option<u32>,
+    /// Initial backoff in milliseconds, doubled on every retry.
+    pub base_delay_ms: Option<u64>,
+    /// Longest single wait in milliseconds, including `Retry-After`.
+    pub max_delay_ms: Option<u64>,
+    /// Requests in flight per endpoint.
+    pub concurrency: Option<usize>,
+    pub requests_per_second: Option<f64>,
+}
+
+/// Caps on what one server may return while its entities are listed.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LimitsConfig {
+    /// List requests per server, over tools, prompts and resources.
+    pub max_pages: Option<usize>,
+    pub max_entities: Option<usize>,
+    /// Longer descriptions are truncated.
+    pub max_description_bytes: Option<usize>,
+    /// Serialized size of all entities of one server.
+    pub max_server_bytes: Option<usize>,
+}
+
+/// Which verifiers run and how their votes are combined.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VerificationConfig {
+    pub verifiers: Option<Vec<VerifierKind>>,
+    pub fusion: Option<Fusion>,
+    pub threshold: Option<f64>,
+    /// Vote weight per verifier for weighted fusion; 1.0 when missing.
+    pub weights: HashMap<VerifierKind, f64>,
+    /// Organisation policy file, see `policy.rs`.
+    pub policy: Option<String>,
+}
+
+/// Signed whitelist and policy bundles, see `bundle.rs`.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BundlesConfig {
+    /// Keys whose bundles are honored.
+    pub trusted_signers: Vec<TrustedSigner>,
+    /// Bundles loaded on every run, besides the imported ones.
+    pub paths: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TrustedSigner {
+    pub name: String,
+    /// Base64 ed25519 public key.
+    pub key: String,
+}
+
+impl Config {
+    /// `~/.config/mcp-scan/config.toml` on Linux, the platform equivalent elsewhere.
+    pub fn default_path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "mcp-scan").map(|dirs| dirs.config_dir().join("config.toml"))
+    }
+
+    /// Load `path`, or the default location when `path` is `None`.
+    /// A missing default file is not an error; a missing explicit one is.
+    pub fn load(path: Option<&str>) -> anyhow::Result<Self> {
+        let path = match path {
+            Some(path) => PathBuf::from(shellexpand::tilde(path).into_owned()),
+            None => match Self::default_path() {
+                Some(path) if path.is_file() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+        let data = fs::read_to_string(&path)
+            .with_context(|| format!("could not read config file {:?}", path))?;
+        toml::from_str(&data).with_context(|| format!("invalid config file {:?}", path))
+    }
+}
+
+/// LLM settings after merging CLI flags, environment variables, the config file and defaults,
+/// in that order of precedence.
+#[derive(Debug, Clone)]
+pub struct LLMSettings {
+    pub provider: LLMProvider,
+    pub api_key: Option<String>,
+    pub api_url: String,
+    pub model: String,
+    pub temperature: f32,
+    pub max_tokens: Option<u32>,
+    pub timeout: Duration,
+    pub token_budget: usize,
+    pub judge_prompt: String,
+    pub translate_prompt: String,
+}
+
+fn load_prompt(inline: Option<String>, file: Option<String>, default: &str) -> anyhow::Result<String> {
+    if let Some(file) = file {
+        let path = shellexpand::tilde(&file).into_owned();
+        return fs::read_to_string(&path).with_context(|| format!("could not read prompt file {}", path));
+    }
+    Ok(inline.unwrap_or_else(|| default.to_string()))
+}
+
+impl LLMSettings {
+    pub fn resolve(args: &CommonArgs, config: &Config) -> anyhow::Result<Self> {
+        let llm = config.llm.clone();
+        let provider = args.llm_provider.or(llm.provider).unwrap_or_default();
+        Ok(Self {
+            provider,
+            api_key: args.llm_api_key.clone().or(llm.api_key),
+            api_url: args
+                .llm_api_url
+                .clone()
+                .or(llm.api_url)
+                .unwrap_or_else(|| provider.default_api_url().to_string()),
+            model: args
+                .llm_model
+                .clone()
+                .or(llm.model)
+                .unwrap_or_else(|| DEFAULT_LLM_MODEL.to_string()),
+            temperature: args.llm_temperature.or(llm.temperature).unwrap_or(DEFAULT_LLM_TEMPERATURE),
+            max_tokens: args.llm_max_tokens.or(llm.max_tokens),
+            timeout: Duration::from_secs(args.llm_timeout.or(llm.timeout).unwrap_or(DEFAULT_LLM_TIMEOUT_SECS)),
+            token_budget: args.llm_token_budget.or(llm.token_budget).unwrap_or(DEFAULT_TOKEN_BUDGET),
+            judge_prompt: load_prompt(
+                llm.judge_prompt,
+                args.llm_judge_prompt_file.clone().or(llm.judge_prompt_file),
+                JUDGE_SYSTEM_PROMPT,
+            )?,
+            translate_prompt: load_prompt(
+                llm.translate_prompt,
+                args.llm_translate_prompt_file.clone().or(llm.translate_prompt_file),
+                DEFAULT_TRANSLATE_PROMPT,
+            )?,
+        })
+    }
+
+    /// A chat client for the configured provider.
+    pub fn client(&self, http: &HttpSettings) -> Arc<dyn ChatClient> {
+        let api_key = self.api_key.clone().unwrap_or_default();
+        let url = Some(self.api_url.clone());
+        match self.provider {
+            LLMProvider::OpenAI => Arc::new(
+                OpenAIClient::new(api_key, url, None).with_timeout(self.timeout).with_http(http.clone()),
+            ),
+            LLMProvider::Anthropic => Arc::new(
+                AnthropicClient::new(api_key, url, None).with_timeout(self.timeout).with_http(http.clone()),
+            ),
+            LLMProvider::Ollama => Arc::new(
+                OllamaClient::new(url, None).with_timeout(self.timeout).with_http(http.clone()),
+            ),
+        }
+    }
+}
+
+/// Resolved `HttpConfig`, shared by every endpoint.
+#[derive(Debug, Clone)]
+pub struct HttpSettings {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub concurrency: usize,
+    pub requests_per_second: Option<f64>,
+}
+
+impl Default for HttpSettings {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_HTTP_MAX_RETRIES,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            concurrency: DEFAULT_HTTP_CONCURRENCY,
+            requests_per_second: None,
+        }
+    }
+}
+
+impl HttpSettings {
+    pub fn resolve(args: &CommonArgs, config: &Config) -> Self {
+        let http = &config.http;
+        let default = Self::default();
+        Self {
+            max_retries: args.http_retries.or(http.max_retries).unwrap_or(default.max_retries),
+            base_delay: http.base_delay_ms.map(Duration::from_millis).unwrap_or(default.base_delay),
+            max_delay: http.max_delay_ms.map(Duration::from_millis).unwrap_or(default.max_delay),
+            concurrency: args.http_concurrency.or(http.concurrency).unwrap_or(default.concurrency),
+            requests_per_second: args.http_rate_limit.or(http.requests_per_second),
+        }
+    }
+}
+
+/// Resolved `LimitsConfig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_pages: usize,
+    pub max_entities: usize,
+    pub max_description_bytes: usize,
+    pub max_server_bytes: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_pages: DEFAULT_MAX_PAGES,
+            max_entities: DEFAULT_MAX_ENTITIES,
+            max_description_bytes: DEFAULT_MAX_DESCRIPTION_BYTES,
+            max_server_bytes: DEFAULT_MAX_SERVER_BYTES,
+        }
+    }
+}
+
+impl Limits {
+    pub fn resolve(args: &CommonArgs, config: &Config) -> Self {
+        let limits = &config.limits;
+        let default = Self::default();
+        Self {
+            max_pages: args.max_pages.or(limits.max_pages).unwrap_or(default.max_pages),
+            max_entities: args.max_entities.or(limits.max_entities).unwrap_or(default.max_entities),
+            max_description_bytes: args
+                .max_description_bytes
+                .or(limits.max_description_bytes)
+                .unwrap_or(default.max_description_bytes),
+            max_server_bytes: args.max_server_bytes.or(limits.max_server_bytes).unwrap_or(default.max_server_bytes),
+        }
+    }
+}
+
+/// Resolved `VerificationConfig`.
+#[derive(Debug, Clone)]
+pub struct VerificationSettings {
+    pub verifiers: Vec<VerifierKind>,
+    pub fusion: Fusion,
+    pub threshold: f64,
+    pub weights: HashMap<VerifierKind, f64>,
+    pub policy: Option<Arc<Policy>>,
+}
+
+impl Default for VerificationSettings {
+    fn default() -> Self {
+        Self {
+            verifiers: vec![VerifierKind::Remote, VerifierKind::Heuristics],
+            fusion: Fusion::default(),
+            threshold: DEFAULT_FUSION_THRESHOLD,
+            weights: HashMap::new(),
+            policy: None,
+        }
+    }
+}
+
+impl VerificationSettings {
+    /// The defaults, leaving out the remote verifier when there is no
+    /// verification API at `base_url` to ask.
+    pub fn default_for(base_url: &str) -> Self {
+        let mut settings = Self::default();
+        if base_url.is_empty() {
+            settings.verifiers.retain(|kind| *kind != VerifierKind::Remote);
+        }
+        settings
+    }
+
+    /// A policy file enables the policy verifier even when it is not listed.
+    /// The threshold must be in (0, 1] and weights finite and not negative.
+    /// Listing the remote verifier without `--base-url` is an error.
+    pub fn resolve(args: &CommonArgs, config: &Config) -> anyhow::Result<Self> {
+        let verification = config.verification.clone();
+        let default = Self::default_for(&args.base_url);
+        let mut weights = verification.weights;
+        weights.extend(args.verifier_weight.iter().copied());
+        for (kind, weight) in &weights {
+            if !weight.is_finite() || *weight < 0.0 {
+                anyhow::bail!("invalid weight {} for the {} verifier, expected a number of at least 0", weight, kind);
+            }
+        }
+        let threshold = args.fusion_threshold.or(verification.threshold).unwrap_or(default.threshold);
+        // 阈值为 0 时任何一票都会标记实体
+        if !(threshold > 0.0 && threshold <= 1.0) {
+            anyhow::bail!("invalid fusion threshold {}, expected a number in (0, 1]", threshold);
+        }
+        let policy = match args.policy.as_ref().or(verification.policy.as_ref()) {
+            Some(path) => Some(Policy::load(path)?),
+            None => None,
+        };
+        let verifiers = args.verifiers.clone().or(verification.verifiers).unwrap_or(default.verifiers);
+        // 没有验证API时远程验证只会让每个实体都无法验证
+        if verifiers.contains(&VerifierKind::Remote) && args.base_url.is_empty() {
+            anyhow::bail!("the remote verifier needs a verification API; set --base-url or leave remote out of the verifiers");
+        }
+        let settings = Self {
+            verifiers,
+            fusion: args.fusion.or(verification.fusion).unwrap_or(default.fusion),
+            threshold,
+            weights,
+            policy: None,
+        };
+        Ok(match policy {
+            Some(policy) => settings.with_policy(policy),
+            None => settings,
+        })
+    }
+
+    /// Evaluate `policy`, enabling the policy verifier.
+    pub fn with_policy(mut self, policy: Policy) -> Self {
+        if !self.verifiers.contains(&VerifierKind::Policy) {
+            self.verifiers.push(VerifierKind::Policy);
+        }
+        self.policy = Some(Arc::new(policy));
+        self
+    }
+
+    pub fn weight(&self, kind: VerifierKind) -> f64 {
+        self.weights.get(&kind).copied().unwrap_or(1.0)
+    }
+}
+
+/// Everything resolved from the command line, the environment and the config file.
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub llm: LLMSettings,
+    pub http: HttpSettings,
+    pub limits: Limits,
+    pub verification: VerificationSettings,
+    /// Whitelist entries from signed bundles.
+    pub shared_whitelist: Vec<WhitelistEntry>,
+}
+
+impl Settings {
+    /// Load the config file named by `args` and merge it with `args`,
+    /// then apply the signed bundles. A bundle that does not verify is an error.
+    pub fn resolve(args: &CommonArgs) -> anyhow::Result<Self> {
+        let config = Config::load(args.config.as_deref())?;
+        let mut verification = VerificationSettings::resolve(args, &config)?;
+
+        let paths = [config.bundles.paths.as_slice(), args.bundle.as_slice()].concat();
+        let bundles = bundle::load_all(&args.storage_file, &paths, &config.bundles.trusted_signers)?;
+        let mut shared_whitelist = Vec::new();
+        for bundle in bundles {
+            if let Some(policy) = &bundle.contents.policy {
+                if verification.policy.is_some() {
+                    anyhow::bail!("bundle {:?} carries a policy, but another policy is already in effect", bundle.path);
+                }
+                verification = verification.with_policy(Policy::parse(policy)?);
+            }
+            shared_whitelist.extend(bundle.contents.whitelist);
+        }
+
+        Ok(Self {
+            llm: LLMSettings::resolve(args, &config)?,
+            http: HttpSettings::resolve(args, &config),
+            limits: Limits::resolve(args, &config),
+            verification,
+            shared_whitelist,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        common: CommonArgs,
+    }
+
+    #[test]
+    fn test_cli_overrides_config_file() -> anyhow::Result<()> {
+        let config: Config = toml::from_str(
+            r#"
+            [llm]
+            model = "from-config"
+            temperature = 0.1
+            max_tokens = 512
+            translate_prompt = "translate to {language}"
+            "#,
+        )?;
+        let cli = TestCli::try_parse_from(["test", "--llm-model", "from-cli", "--llm-provider", "ollama"])?;
+        let settings = LLMSettings::resolve(&cli.common, &config)?;
+
+        assert_eq!(settings.provider, LLMProvider::Ollama);
+        assert_eq!(settings.api_url, OLLAMA_API_URL);
+        assert_eq!(settings.model, "from-cli");
+        assert_eq!(settings.temperature, 0.1);
+        assert_eq!(settings.max_tokens, Some(512));
+        assert_eq!(settings.translate_prompt, "translate to {language}");
+        assert_eq!(settings.judge_prompt, JUDGE_SYSTEM_PROMPT);
+
+        let http = HttpSettings::resolve(&cli.common, &config);
+        assert_eq!(http.max_retries, DEFAULT_HTTP_MAX_RETRIES);
+        assert_eq!(http.requests_per_second, None);
+        assert_eq!(Limits::resolve(&cli.common, &config), Limits::default());
+        Ok(())
+    }
+
+    #[test]
+    fn test_verification_settings() -> anyhow::Result<()> {
+        let config: Config = toml::from_str(
+            r#"
+            [verification]
+            verifiers = ["remote", "llm"]
+            fusion = "weighted"
+            weights = { remote = 2.0, llm = 0.5 }
+            "#,
+        )?;
+        let cli = TestCli::try_parse_from([
+            "test",
+            "--base-url",
+            "http://localhost:8787",
+            "--fusion",
+            "majority",
+            "--verifier-weight",
+            "llm=3",
+        ])?;
+        let settings = VerificationSettings::resolve(&cli.common, &config)?;
+        assert_eq!(settings.verifiers, [VerifierKind::Remote, VerifierKind::Llm]);
+        assert_eq!(settings.fusion, Fusion::Majority);
+        assert_eq!(settings.weight(VerifierKind::Remote), 2.0);
+        assert_eq!(settings.weight(VerifierKind::Llm), 3.0);
+        assert_eq!(settings.weight(VerifierKind::Policy), 1.0);
+        assert!(TestCli::try_parse_from(["test", "--verifier-weight", "llm"]).is_err());
+
+        // 没有 --base-url 时默认不启用远程验证，明确要求则报错
+        let cli = TestCli::try_parse_from(["test"])?;
+        let settings = VerificationSettings::resolve(&cli.common, &Config::default())?;
+        assert_eq!(settings.verifiers, [VerifierKind::Heuristics]);
+        assert!(VerificationSettings::resolve(&cli.common, &config).is_err());
+        let cli = TestCli::try_parse_from(["test", "--base-url", "http://localhost:8787"])?;
+        let settings = VerificationSettings::resolve(&cli.common, &Config::default())?;
+        assert_eq!(settings.verifiers, [VerifierKind::Remote, VerifierKind::Heuristics]);
+
+        for args in [
+            ["test", "--fusion-threshold", "0"],
+            ["test", "--fusion-threshold", "1.5"],
+            ["test", "--fusion-threshold", "NaN"],
+            ["test", "--verifier-weight", "llm=-1"],
+            ["test", "--verifier-weight", "llm=inf"],
+        ] {
+            let cli = TestCli::try_parse_from(args)?;
+            assert!(VerificationSettings::resolve(&cli.common, &Config::default()).is_err(), "{:?}", args);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        assert!(toml::from_str::<Config>("[llm]\nmodle = \"typo\"").is_err());
+    }
+}
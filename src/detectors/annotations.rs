@@ -0,0 +1,280 @@
+use serde_json::Value;
+
+use crate::detectors::entity_texts;
+use crate::detectors::schema::parameters;
+use crate::mcp_types::{Entity, Finding, Severity};
+
+const DETECTOR: &str = "annotations";
+
+/// Verbs that mean the tool changes state somewhere.
+const WRITE_VERBS: &[&str] = &[
+    "write", "update", "create", "insert", "send", "post", "put", "patch", "exec", "execute", "run",
+    "move", "rename", "upload", "set", "modify", "edit", "push", "commit", "deploy", "transfer",
+    "pay", "install", "save", "publish", "merge", "approve",
+];
+
+/// Verbs that mean the tool removes or irreversibly overwrites data.
+const DESTRUCTIVE_VERBS: &[&str] = &[
+    "delete", "remove", "drop", "truncate", "destroy", "kill", "purge", "wipe", "overwrite", "reset",
+    "erase", "revoke", "terminate", "uninstall",
+];
+
+/// Verbs whose repeated calls have additional effects.
+const NON_IDEMPOTENT_VERBS: &[&str] = &[
+    "create", "send", "post", "append", "insert", "increment", "transfer", "pay", "publish", "comment",
+];
+
+/// Parameter names that let the caller pick an arbitrary remote target.
+const URL_PARAM_NAMES: &[&str] = &["url", "uri", "endpoint", "href", "host", "hostname", "webhook", "link"];
+
+/// Hints declared by a tool. Read from JSON so every `rmcp` revision is handled the same way.
+#[derive(Debug, Default)]
+struct Hints {
+    read_only: Option<bool>,
+    destructive: Option<bool>,
+    idempotent: Option<bool>,
+    open_world: Option<bool>,
+}
+
+impl Hints {
+    fn from_entity(entity: &Entity) -> Option<Self> {
+        let Entity::Tool(tool) = entity else {
+            return None;
+        };
+        let value = serde_json::to_value(tool).ok()?;
+        let annotations = value.get("annotations")?;
+        let flag = |key: &str| annotations.get(key).and_then(|v| v.as_bool());
+        Some(Self {
+            read_only: flag("readOnlyHint"),
+            destructive: flag("destructiveHint"),
+            idempotent: flag("idempotentHint"),
+            open_world: flag("openWorldHint"),
+        })
+    }
+}
+
+/// Split `delete_file`, `deleteFile` or `delete-file` into lowercase words.
+fn name_words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if !c.is_alphanumeric() {
+            if !current.is_empty() {
+                words.push(std::mem::take(&mut current));
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower && !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase();
+        current.extend(c.to_lowercase());
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    words
+}
+
+/// The leading verb of the name (`delete_file`) and of the description
+/// ("Deletes a file ..."), the latter also without its third-person ending.
+/// Later words are usually objects, and plural nouns such as `posts` or
+/// `updates` would otherwise read as verbs.
+fn leading_verbs(entity: &Entity) -> Vec<String> {
+    let mut verbs: Vec<String> = name_words(&entity.name()).into_iter().take(1).collect();
+    let description = entity.description().unwrap_or_default();
+    if let Some(word) = description.split_whitespace().flat_map(name_words).next() {
+        for suffix in ["es", "s"] {
+            if let Some(stem) = word.strip_suffix(suffix) {
+                verbs.push(stem.to_string());
+            }
+        }
+        verbs.push(word);
+    }
+    verbs
+}
+
+fn matching_verb(words: &[String], verbs: &[&'static str]) -> Option<&'static str> {
+    words.iter().find_map(|word| verbs.iter().find(|v| **v == word).copied())
+}
+
+fn takes_url(entity: &Entity) -> Option<String> {
+    parameters(entity).into_iter().find_map(|param| {
+        let named = URL_PARAM_NAMES.contains(&name_words(&param.name).join("").as_str())
+            || name_words(&param.name).last().is_some_and(|w| URL_PARAM_NAMES.contains(&w.as_str()));
+        let formatted = matches!(
+            param.schema.get("format").and_then(Value::as_str),
+            Some("uri") | Some("url") | Some("iri")
+        );
+        (named || formatted).then_some(param.path)
+    })
+}
+
+fn mentions_remote(entity: &Entity) -> Option<String> {
+    entity_texts(entity).into_iter().find_map(|(location, text)| {
+        let lower = text.to_lowercase();
+        (lower.contains("http://") || lower.contains("https://") || lower.contains("any url"))
+            .then_some(location)
+    })
+}
+
+/// Compare a tool's behavioural hints with what its name, description and schema imply.
+///
+/// Clients use these hints to auto-approve calls, so a mismatch directly weakens the
+/// user's confirmation step.
+pub fn check(entity: &Entity) -> Vec<Finding> {
+    let Some(hints) = Hints::from_entity(entity) else {
+        return vec![];
+    };
+    let words = leading_verbs(entity);
+    let mut findings = Vec::new();
+
+    if hints.read_only == Some(true) {
+        let verb = matching_verb(&words, DESTRUCTIVE_VERBS).or_else(|| matching_verb(&words, WRITE_VERBS));
+        if let Some(verb) = verb {
+            findings.push(
+                Finding::new(
+                    DETECTOR,
+                    Severity::High,
+                    format!("tool claims readOnlyHint but its name or description implies '{}'", verb),
+                )
+                .with_location("annotations.readOnlyHint"),
+            );
+        }
+        if hints.destructive == Some(true) {
+            findings.push(
+                Finding::new(
+                    DETECTOR,
+                    Severity::Medium,
+                    "tool claims both readOnlyHint and destructiveHint",
+                )
+                .with_location("annotations"),
+            );
+        }
+    }
+
+    if let (Some(false), Some(verb)) = (hints.destructive, matching_verb(&words, DESTRUCTIVE_VERBS)) {
+        findings.push(
+            Finding::new(
+                DETECTOR,
+                Severity::High,
+                format!("tool claims destructiveHint: false but its name or description implies '{}'", verb),
+            )
+            .with_location("annotations.destructiveHint"),
+        );
+    }
+
+    if let (Some(true), Some(verb)) = (hints.idempotent, matching_verb(&words, NON_IDEMPOTENT_VERBS)) {
+        findings.push(
+            Finding::new(
+                DETECTOR,
+                Severity::Medium,
+                format!("tool claims idempotentHint but its name or description implies '{}'", verb),
+            )
+            .with_location("annotations.idempotentHint"),
+        );
+    }
+
+    if hints.open_world == Some(false) {
+        if let Some(path) = takes_url(entity) {
+            findings.push(
+                Finding::new(
+                    DETECTOR,
+                    Severity::High,
+                    "tool claims openWorldHint: false but accepts an arbitrary URL",
+                )
+                .with_location("annotations.openWorldHint")
+                .with_evidence(path),
+            );
+        } else if let Some(location) = mentions_remote(entity) {
+            findings.push(
+                Finding::new(
+                    DETECTOR,
+                    Severity::Medium,
+                    "tool claims openWorldHint: false but its text refers to remote URLs",
+                )
+                .with_location("annotations.openWorldHint")
+                .with_evidence(location),
+            );
+        }
+    }
+
+    findings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(name: &str, description: &str, schema: Value, annotations: Value) -> Entity {
+        Entity::Tool(
+            serde_json::from_value(serde_json::json!({
+                "name": name,
+                "description": description,
+                "inputSchema": schema,
+                "annotations": annotations,
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_name_words() {
+        assert_eq!(name_words("deleteFile"), vec!["delete", "file"]);
+        assert_eq!(name_words("delete_file-now"), vec!["delete", "file", "now"]);
+        assert_eq!(name_words("HTTPGet"), vec!["httpget"]);
+    }
+
+    #[test]
+    fn test_read_only_delete_is_flagged() {
+        let entity = tool(
+            "delete_file",
+            "Removes a file",
+            serde_json::json!({ "type": "object" }),
+            serde_json::json!({ "readOnlyHint": true }),
+        );
+        let findings = check(&entity);
+        assert!(findings.iter().any(|f| f.severity == Severity::High));
+    }
+
+    #[test]
+    fn test_closed_world_with_url_is_flagged() {
+        let entity = tool(
+            "get_page",
+            "Returns a page",
+            serde_json::json!({ "type": "object", "properties": { "target_url": { "type": "string" } } }),
+            serde_json::json!({ "openWorldHint": false, "readOnlyHint": true }),
+        );
+        let findings = check(&entity);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].evidence.as_deref(), Some("inputSchema.properties.target_url"));
+    }
+
+    #[test]
+    fn test_consistent_annotations_pass() {
+        let entity = tool(
+            "read_file",
+            "Reads a file",
+            serde_json::json!({ "type": "object", "properties": { "path": { "type": "string" } } }),
+            serde_json::json!({ "readOnlyHint": true, "openWorldHint": false, "destructiveHint": false }),
+        );
+        assert!(check(&entity).is_empty());
+
+        // 复数名词不是动词
+        for (name, description) in [
+            ("list_posts", "Lists posts in a channel"),
+            ("get_commits", "Returns the commits of a branch"),
+            ("get_updates", "Gets updates since the last call"),
+        ] {
+            let entity = tool(
+                name,
+                description,
+                serde_json::json!({ "type": "object" }),
+                serde_json::json!({ "readOnlyHint": true, "idempotentHint": true }),
+            );
+            assert!(check(&entity).is_empty(), "{}", name);
+        }
+    }
+}
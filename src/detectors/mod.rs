@@ -0,0 +1,92 @@
+pub mod annotations;
+pub mod language;
+pub mod obfuscation;
+pub mod schema;
+pub mod toxic_flow;
+
+use crate::mcp_types::{Entity, Finding, Severity};
+
+/// Phrases that instruct the model to act behind the user's back.
+const COVERT_INSTRUCTIONS: &[&str] = &[
+    "<important>",
+    "ignore previous",
+    "ignore all previous",
+    "do not tell",
+    "don't tell",
+    "do not mention",
+    "don't mention",
+    "do not inform",
+    "without telling",
+    "without informing",
+    "keep this secret",
+    "before using this tool",
+    "otherwise the tool will not work",
+];
+
+/// Locations and kinds of data a tool has no business asking the model for.
+const SENSITIVE_TARGETS: &[&str] = &[
+    "~/.ssh",
+    "id_rsa",
+    ".env",
+    "mcp.json",
+    "~/.aws",
+    "/etc/passwd",
+    "private key",
+    "api key",
+    "api_key",
+    "password",
+    "credential",
+    "secret",
+    "cookie",
+    "system prompt",
+    "conversation history",
+];
+
+/// Every piece of text of an entity that ends up in a model's context:
+/// the description followed by all text found in its schema, as `(location, text)`.
+pub fn entity_texts(entity: &Entity) -> Vec<(String, String)> {
+    let mut texts = Vec::new();
+    if let Some(description) = entity.description() {
+        texts.push(("description".to_string(), description));
+    }
+    texts.extend(
+        schema::schema_texts(entity)
+            .into_iter()
+            .map(|t| (t.path, t.text)),
+    );
+    texts
+}
+
+/// Match `text` against the shared instruction and sensitive-data phrase lists.
+/// Returns the severity and the phrase that matched first.
+pub fn match_suspicious_phrase(text: &str) -> Option<(Severity, &'static str)> {
+    let lower = text.to_lowercase();
+    if let Some(phrase) = COVERT_INSTRUCTIONS.iter().find(|p| lower.contains(*p)) {
+        return Some((Severity::High, phrase));
+    }
+    SENSITIVE_TARGETS
+        .iter()
+        .find(|p| lower.contains(*p))
+        .map(|phrase| (Severity::Medium, *phrase))
+}
+
+/// Run all local detectors on a single entity.
+pub fn check_entity(entity: &Entity) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    findings.extend(schema::check(entity));
+    findings.extend(annotations::check(entity));
+    findings.extend(language::check(entity));
+    findings.extend(obfuscation::check(entity));
+    findings
+}
+
+/// Shorten `text` to at most `max` characters for display in a finding.
+pub fn excerpt(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        text.to_string()
+    } else {
+        let mut s: String = text.chars().take(max).collect();
+        s.push('…');
+        s
+    }
+}
@@ -0,0 +1,287 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::detectors::schema::parameters;
+use crate::mcp_types::{Entity, Finding, Severity};
+
+pub const DETECTOR: &str = "toxic-flow";
+
+/// Flows kept per client config. Every combination of three tool lists is a
+/// flow, so a server with many tools could otherwise produce millions.
+pub const MAX_FLOWS: usize = 1000;
+
+/// Verbs that pull content into the model's context.
+const READ_VERBS: &[&str] = &[
+    "read", "get", "list", "search", "fetch", "query", "browse", "open", "view", "show", "find",
+    "download", "load", "retrieve", "scrape", "crawl", "navigate", "select",
+];
+
+/// Verbs that push content somewhere outside the machine.
+const SEND_VERBS: &[&str] = &[
+    "send", "post", "upload", "publish", "share", "push", "submit", "reply", "forward", "tweet",
+    "notify", "comment", "create", "email",
+];
+
+/// Verbs that on their own mean reading arbitrary remote content.
+const WEB_VERBS: &[&str] = &["fetch", "browse", "scrape", "crawl", "navigate"];
+
+const UNTRUSTED_NOUNS: &[&str] = &[
+    "web", "url", "page", "webpage", "website", "html", "browser", "email", "mail", "inbox",
+    "message", "issue", "comment", "pr", "pull", "tweet", "rss", "feed", "news", "slack", "discord",
+    "channel", "internet",
+];
+
+const PRIVATE_NOUNS: &[&str] = &[
+    "file", "directory", "dir", "folder", "filesystem", "fs", "database", "db", "sql", "table",
+    "row", "record", "secret", "credential", "env", "password", "document", "doc", "note",
+    "calendar", "contact", "drive", "memory", "clipboard", "history", "keychain", "repository",
+    "repo",
+];
+
+const SINK_NOUNS: &[&str] = &[
+    "email", "mail", "message", "slack", "webhook", "http", "request", "issue", "comment", "tweet",
+    "channel", "discord", "sms", "gist", "pr",
+];
+
+/// The role a tool can play in a data flow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// Reads content an attacker can control: web pages, email, issues, chat.
+    UntrustedInput,
+    /// Reads data the user would not want to leak: files, databases, secrets.
+    PrivateData,
+    /// Sends data off the machine: HTTP, email, chat, issue trackers.
+    Exfiltration,
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Capability::UntrustedInput => "untrusted input",
+            Capability::PrivateData => "private data",
+            Capability::Exfiltration => "exfiltration",
+        })
+    }
+}
+
+/// A tool on a particular server.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct ToolRef {
+    pub server: String,
+    pub tool: String,
+}
+
+impl fmt::Display for ToolRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.server, self.tool)
+    }
+}
+
+/// Three tools that together let injected instructions read private data and send it out.
+#[derive(Debug, Clone, Serialize)]
+pub struct ToxicFlow {
+    pub untrusted_input: ToolRef,
+    pub private_data: ToolRef,
+    pub exfiltration: ToolRef,
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .flat_map(split_camel_case)
+        .map(|w| singular(&w.to_lowercase()))
+        .collect()
+}
+
+fn split_camel_case(word: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut prev_lower = false;
+    for c in word.chars() {
+        if c.is_uppercase() && prev_lower {
+            parts.push(std::mem::take(&mut current));
+        }
+        prev_lower = c.is_lowercase();
+        current.push(c);
+    }
+    parts.push(current);
+    parts
+}
+
+fn singular(word: &str) -> String {
+    if word.len() > 3 && word.ends_with("ies") {
+        format!("{}y", &word[..word.len() - 3])
+    } else if word.len() > 3 && word.ends_with('s') && !word.ends_with("ss") {
+        word[..word.len() - 1].to_string()
+    } else {
+        word.to_string()
+    }
+}
+
+fn any_of(words: &[String], set: &[&str]) -> bool {
+    words.iter().any(|w| set.contains(&w.as_str()))
+}
+
+/// Classify a tool by what it can do, from its name, the start of its description
+/// and whether it accepts an arbitrary URL. Prompts and resources have no capabilities.
+pub fn classify(entity: &Entity) -> Vec<Capability> {
+    if !matches!(entity, Entity::Tool(_)) {
+        return vec![];
+    }
+    let name_words = words(&entity.name());
+    let description = entity.description().unwrap_or_default();
+    let leading: String = description.split_whitespace().take(12).collect::<Vec<_>>().join(" ");
+    let mut all_words = name_words.clone();
+    all_words.extend(words(&leading));
+
+    let reads = any_of(&all_words, READ_VERBS);
+    let sends = any_of(&name_words, SEND_VERBS) || (any_of(&all_words, SEND_VERBS) && !reads);
+    let takes_url = parameters(entity).iter().any(|p| {
+        let param_words = words(&p.name);
+        param_words.last().is_some_and(|w| w == "url" || w == "uri" || w == "webhook")
+    });
+
+    let mut capabilities = Vec::new();
+    if any_of(&name_words, WEB_VERBS) || (reads && any_of(&all_words, UNTRUSTED_NOUNS)) {
+        capabilities.push(Capability::UntrustedInput);
+    }
+    if reads && any_of(&all_words, PRIVATE_NOUNS) {
+        capabilities.push(Capability::PrivateData);
+    }
+    // 能访问任意 URL 的工具可以通过查询参数把数据带出去
+    if takes_url || (sends && any_of(&all_words, SINK_NOUNS)) || any_of(&name_words, &["send", "upload", "post", "publish", "tweet"]) {
+        capabilities.push(Capability::Exfiltration);
+    }
+    capabilities
+}
+
+/// The toxic flows of one client config, cut at `MAX_FLOWS`.
+#[derive(Debug, Clone, Default)]
+pub struct Flows {
+    pub flows: Vec<ToxicFlow>,
+    /// Number of flows before the cut.
+    pub total: usize,
+}
+
+impl Flows {
+    /// A finding when flows were left out of the list.
+    pub fn truncation(&self) -> Option<Finding> {
+        (self.total > self.flows.len()).then(|| {
+            Finding::new(
+                DETECTOR,
+                Severity::Medium,
+                format!("toxic flow list truncated to {} of {} flows", self.flows.len(), self.total),
+            )
+        })
+    }
+}
+
+/// Find the untrusted-input → private-data → exfiltration combinations across
+/// all servers of one client config, keeping at most `MAX_FLOWS` of them.
+pub fn analyze(servers: &HashMap<String, Vec<Entity>>) -> Flows {
+    let mut by_capability: HashMap<Capability, Vec<ToolRef>> = HashMap::new();
+    for (server, entities) in servers {
+        for entity in entities {
+            for capability in classify(entity) {
+                by_capability.entry(capability).or_default().push(ToolRef {
+                    server: server.clone(),
+                    tool: entity.name(),
+                });
+            }
+        }
+    }
+    for tools in by_capability.values_mut() {
+        tools.sort();
+    }
+
+    let empty = Vec::new();
+    let untrusted = by_capability.get(&Capability::UntrustedInput).unwrap_or(&empty);
+    let private = by_capability.get(&Capability::PrivateData).unwrap_or(&empty);
+    let sinks = by_capability.get(&Capability::Exfiltration).unwrap_or(&empty);
+
+    let flows = untrusted
+        .iter()
+        .flat_map(|u| private.iter().flat_map(move |p| sinks.iter().map(move |e| (u, p, e))))
+        .take(MAX_FLOWS)
+        .map(|(u, p, e)| ToxicFlow {
+            untrusted_input: u.clone(),
+            private_data: p.clone(),
+            exfiltration: e.clone(),
+        })
+        .collect();
+    Flows {
+        flows,
+        total: untrusted.len().saturating_mul(private.len()).saturating_mul(sinks.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(name: &str, description: &str, properties: serde_json::Value) -> Entity {
+        Entity::Tool(
+            serde_json::from_value(serde_json::json!({
+                "name": name,
+                "description": description,
+                "inputSchema": { "type": "object", "properties": properties },
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_classify() {
+        let fetch = tool("fetch", "Fetches a URL from the internet", serde_json::json!({ "url": { "type": "string" } }));
+        assert_eq!(classify(&fetch), vec![Capability::UntrustedInput, Capability::Exfiltration]);
+
+        let read_file = tool("read_file", "Read the complete contents of a file", serde_json::json!({ "path": { "type": "string" } }));
+        assert_eq!(classify(&read_file), vec![Capability::PrivateData]);
+
+        let send = tool("send_message", "Send a message to a Slack channel", serde_json::json!({}));
+        assert_eq!(classify(&send), vec![Capability::Exfiltration]);
+
+        let add = tool("add", "Adds two numbers", serde_json::json!({}));
+        assert!(classify(&add).is_empty());
+    }
+
+    #[test]
+    fn test_flow_across_servers() {
+        let mut servers = HashMap::new();
+        servers.insert(
+            "web".to_string(),
+            vec![tool("fetch", "Fetches a URL from the internet", serde_json::json!({ "url": { "type": "string" } }))],
+        );
+        servers.insert(
+            "fs".to_string(),
+            vec![tool("read_file", "Read the complete contents of a file", serde_json::json!({}))],
+        );
+        let flows = analyze(&servers).flows;
+        assert_eq!(flows.len(), 1);
+        assert_eq!(flows[0].private_data.to_string(), "fs/read_file");
+        assert_eq!(flows[0].exfiltration.to_string(), "web/fetch");
+
+        servers.remove("fs");
+        assert!(analyze(&servers).flows.is_empty());
+    }
+
+    #[test]
+    fn test_flows_are_capped() {
+        let many = |verb: &str, noun: &str| {
+            (0..20)
+                .map(|i| tool(&format!("{}_{}_{}", verb, noun, i), "", serde_json::json!({})))
+                .collect::<Vec<_>>()
+        };
+        let mut servers = HashMap::new();
+        servers.insert("web".to_string(), many("browse", "page"));
+        servers.insert("fs".to_string(), many("read", "file"));
+        servers.insert("mail".to_string(), many("send", "email"));
+        let flows = analyze(&servers);
+        assert_eq!(flows.flows.len(), MAX_FLOWS);
+        assert_eq!(flows.total, 8000);
+        assert!(flows.truncation().unwrap().message.contains("1000 of 8000"));
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+
+use serde::Serialize;
+
+use crate::config::Limits;
+use crate::mcp_types::{Entity, Finding, Severity};
+
+pub const DETECTOR: &str = "limits";
+/// Two entities with the same `type:name` shadow each other wherever
+/// entities are looked up by id, so listing one twice is suspicious.
+pub const DUPLICATE_DETECTOR: &str = "duplicate-entity";
+
+/// A cap on what one server may return while it is listed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Limit {
+    Pages,
+    Entities,
+    DescriptionBytes,
+    ServerBytes,
+}
+
+impl fmt::Display for Limit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Limit::Pages => "list requests",
+            Limit::Entities => "entities",
+            Limit::DescriptionBytes => "description bytes",
+            Limit::ServerBytes => "bytes of entity data",
+        })
+    }
+}
+
+/// A cap that was hit while listing a server's tools, prompts or resources.
+#[derive(Debug, Clone, Serialize)]
+pub struct LimitHit {
+    pub limit: Limit,
+    /// `tools`, `prompts` or `resources`.
+    pub list: &'static str,
+    pub max: usize,
+    /// The entity whose description was truncated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity: Option<String>,
+}
+
+impl fmt::Display for LimitHit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.limit, &self.entity) {
+            (Limit::DescriptionBytes, Some(entity)) => {
+                write!(f, "description of {} truncated to {} bytes", entity, self.max)
+            }
+            _ => write!(f, "stopped listing {} after {} {}", self.list, self.max, self.limit),
+        }
+    }
+}
+
+impl LimitHit {
+    /// Well-behaved servers stay far below the defaults, so a hit is reported as a finding.
+    pub fn finding(&self) -> Finding {
+        let finding = Finding::new(DETECTOR, Severity::Medium, format!("server exceeded a limit: {}", self));
+        match &self.entity {
+            Some(entity) => finding.with_location(entity.clone()),
+            None => finding.with_location(self.list),
+        }
+    }
+}
+
+/// Entities collected from one server, within `Limits`.
+#[derive(Debug)]
+pub struct Enumeration {
+    limits: Limits,
+    pages: usize,
+    bytes: usize,
+    stopped: bool,
+    pub entities: Vec<Entity>,
+    pub hits: Vec<LimitHit>,
+}
+
+impl Enumeration {
+    pub fn new(limits: Limits) -> Self {
+        Self {
+            limits,
+            pages: 0,
+            bytes: 0,
+            stopped: false,
+            entities: Vec::new(),
+            hits: Vec::new(),
+        }
+    }
+
+    /// Page through one list. `fetch` gets the cursor of the page to request
+    /// and returns its entities and the next cursor. Stops at the first cap hit,
+    /// or when the server hands out a cursor it already used.
+    pub async fn list<F, Fut>(&mut self, list: &'static str, mut fetch: F) -> anyhow::Result<()>
+    where
+        F: FnMut(Option<String>) -> Fut,
+        Fut: Future<Output = anyhow::Result<(Vec<Entity>, Option<String>)>>,
+    {
+        let mut cursor = None;
+        let mut seen = HashSet::new();
+        while !self.stopped {
+            if self.pages >= self.limits.max_pages {
+                self.stop(Limit::Pages, list, self.limits.max_pages);
+                break;
+            }
+            self.pages += 1;
+            let (items, next) = fetch(cursor.take()).await?;
+            self.accept(list, items);
+            match next {
+                Some(next) if seen.insert(next.clone()) => cursor = Some(next),
+                // 重复的游标意味着无限分页
+                Some(_) => self.stop(Limit::Pages, list, self.pages),
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn accept(&mut self, list: &'static str, items: Vec<Entity>) {
+        for mut entity in items {
+            if self.stopped {
+                return;
+            }
+            if self.entities.len() >= self.limits.max_entities {
+                self.stop(Limit::Entities, list, self.limits.max_entities);
+                return;
+            }
+            if entity.truncate_description(self.limits.max_description_bytes) {
+                self.hits.push(LimitHit {
+                    limit: Limit::DescriptionBytes,
+                    list,
+                    max: self.limits.max_description_bytes,
+                    entity: Some(entity.id()),
+                });
+            }
+            let size = serde_json::to_vec(&entity).map(|v| v.len()).unwrap_or_default();
+            if self.bytes + size > self.limits.max_server_bytes {
+                self.stop(Limit::ServerBytes, list, self.limits.max_server_bytes);
+                return;
+            }
+            self.bytes += size;
+            self.entities.push(entity);
+        }
+    }
+
+    /// A finding for each `type:name` listed more than once.
+    pub fn duplicates(&self) -> Vec<Finding> {
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for entity in &self.entities {
+            *counts.entry(entity.id()).or_default() += 1;
+        }
+        counts
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(id, count)| {
+                Finding::new(DUPLICATE_DETECTOR, Severity::High, format!("server lists {} entities named {}", count, id))
+                    .with_location(id)
+            })
+            .collect()
+    }
+
+    fn stop(&mut self, limit: Limit, list: &'static str, max: usize) {
+        self.stopped = true;
+        self.hits.push(LimitHit { limit, list, max, entity: None });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(name: &str, description: &str) -> Entity {
+        Entity::Tool(
+            serde_json::from_value(serde_json::json!({
+                "name": name,
+                "description": description,
+                "inputSchema": { "type": "object" },
+            }))
+            .unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_caps_stop_hostile_servers() -> anyhow::Result<()> {
+        let limits = Limits {
+            max_pages: 5,
+            max_entities: 3,
+            max_description_bytes: 10,
+            max_server_bytes: 1024,
+        };
+
+        // 每页返回同一个游标
+        let mut enumeration = Enumeration::new(limits);
+        enumeration
+            .list("tools", |_| async { Ok((vec![], Some("again".to_string()))) })
+            .await?;
+        assert_eq!(enumeration.hits.len(), 1);
+        assert_eq!(enumeration.hits[0].limit, Limit::Pages);
+
+        // 无穷多的工具，描述过长
+        let mut enumeration = Enumeration::new(limits);
+        let mut page = 0;
+        enumeration
+            .list("tools", |_| {
+                page += 1;
+                let items = vec![tool(&format!("t{}", page), "天气预报，支持全球城市")];
+                async move { Ok((items, Some(page.to_string()))) }
+            })
+            .await?;
+        let mut calls = 0;
+        enumeration
+            .list("prompts", |_| {
+                calls += 1;
+                async { Ok((vec![], None)) }
+            })
+            .await?;
+        assert_eq!(calls, 0);
+        assert_eq!(enumeration.entities.len(), 3);
+        assert_eq!(enumeration.entities[0].description().as_deref(), Some("天气预…[truncated]"));
+        let limits_hit = enumeration.hits.iter().map(|h| h.limit).collect::<Vec<_>>();
+        assert_eq!(
+            limits_hit,
+            [Limit::DescriptionBytes, Limit::DescriptionBytes, Limit::DescriptionBytes, Limit::Entities]
+        );
+        assert_eq!(enumeration.hits[3].finding().severity, Severity::Medium);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_ids() -> anyhow::Result<()> {
+        let mut enumeration = Enumeration::new(Limits::default());
+        enumeration
+            .list("tools", |_| async { Ok((vec![tool("add", "Adds"), tool("add", "Sends"), tool("sub", "")], None)) })
+            .await?;
+        let duplicates = enumeration.duplicates();
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].location.as_deref(), Some("tool:add"));
+        Ok(())
+    }
+}
@@ -0,0 +1,286 @@
+//! Shared HTTP middleware for the LLM and verification endpoints: retries with
+//! exponential backoff and jitter, `Retry-After`, per-endpoint concurrency and
+//! rate limits, and classification of what went wrong.
+
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+
+use reqwest::{RequestBuilder, Response, StatusCode, header::HeaderMap};
+use serde::Serialize;
+use tokio::sync::Semaphore;
+use tokio::time::Instant;
+
+use crate::config::HttpSettings;
+
+/// Longest error body kept in an `HttpError` message.
+const MAX_ERROR_BODY: usize = 300;
+
+/// What kind of failure an HTTP call ended in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ErrorKind {
+    /// Missing or rejected credentials (401, 403)
+    Auth,
+    /// Rate limited or out of credit (429, 402)
+    Quota,
+    /// Connection failures and timeouts
+    Network,
+    /// The endpoint failed (5xx) or answered with something unreadable
+    Server,
+    /// The request itself was rejected (other 4xx) or could not be built
+    Request,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            ErrorKind::Auth => "auth",
+            ErrorKind::Quota => "quota",
+            ErrorKind::Network => "network",
+            ErrorKind::Server => "server",
+            ErrorKind::Request => "request",
+        };
+        f.write_str(kind)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct HttpError {
+    pub kind: ErrorKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    pub message: String,
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.status {
+            Some(status) => write!(f, "{} error (HTTP {}): {}", self.kind, status, self.message),
+            None => write!(f, "{} error: {}", self.kind, self.message),
+        }
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+impl HttpError {
+    pub fn new(kind: ErrorKind, message: impl ToString) -> Self {
+        Self { kind, status: None, message: message.to_string() }
+    }
+
+    pub fn from_status(status: StatusCode, body: &str) -> Self {
+        let kind = match status.as_u16() {
+            401 | 403 => ErrorKind::Auth,
+            402 | 429 => ErrorKind::Quota,
+            408 => ErrorKind::Network,
+            500.. => ErrorKind::Server,
+            _ => ErrorKind::Request,
+        };
+        let mut message: String = body.trim().chars().take(MAX_ERROR_BODY).collect();
+        if message.is_empty() {
+            message = status.canonical_reason().unwrap_or("no response body").to_string();
+        }
+        Self { kind, status: Some(status.as_u16()), message }
+    }
+
+    pub fn from_reqwest(error: &reqwest::Error) -> Self {
+        let kind = if error.is_builder() { ErrorKind::Request } else { ErrorKind::Network };
+        Self::new(kind, error)
+    }
+
+    /// Network and server errors are transient; of the quota errors only 429 is worth waiting for.
+    pub fn is_retryable(&self) -> bool {
+        match self.kind {
+            ErrorKind::Network | ErrorKind::Server => true,
+            ErrorKind::Quota => self.status == Some(429),
+            ErrorKind::Auth | ErrorKind::Request => false,
+        }
+    }
+}
+
+/// Concurrency and pacing shared by every request to the same endpoint.
+struct Limiter {
+    permits: Semaphore,
+    interval: Option<Duration>,
+    next_slot: tokio::sync::Mutex<Instant>,
+}
+
+impl Limiter {
+    fn new(settings: &HttpSettings) -> Self {
+        Self {
+            permits: Semaphore::new(settings.concurrency.max(1)),
+            interval: settings
+                .requests_per_second
+                .filter(|rps| *rps > 0.0)
+                .map(|rps| Duration::from_secs_f64(1.0 / rps)),
+            next_slot: tokio::sync::Mutex::new(Instant::now()),
+        }
+    }
+
+    async fn acquire(&self) -> tokio::sync::SemaphorePermit<'_> {
+        let permit = self.permits.acquire().await.expect("limiter semaphore is never closed");
+        let mut next_slot = self.next_slot.lock().await;
+        tokio::time::sleep_until(*next_slot).await;
+        *next_slot = Instant::now() + self.interval.unwrap_or_default();
+        permit
+    }
+
+    /// Hold back every caller of the endpoint, e.g. after a 429 with `Retry-After`.
+    async fn defer(&self, delay: Duration) {
+        let mut next_slot = self.next_slot.lock().await;
+        *next_slot = (*next_slot).max(Instant::now() + delay);
+    }
+}
+
+/// One limiter per endpoint URL, so the judge, translation and verification
+/// clients talking to the same host share its budget.
+fn limiter(endpoint: &str, settings: &HttpSettings) -> Arc<Limiter> {
+    static LIMITERS: OnceLock<Mutex<HashMap<String, Arc<Limiter>>>> = OnceLock::new();
+    let mut limiters = LIMITERS.get_or_init(Default::default).lock().unwrap_or_else(|e| e.into_inner());
+    limiters
+        .entry(endpoint.to_string())
+        .or_insert_with(|| Arc::new(Limiter::new(settings)))
+        .clone()
+}
+
+/// `Retry-After` as either delay-seconds or an HTTP date.
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (date.with_timezone(&chrono::Utc) - chrono::Utc::now()).to_std().ok()
+}
+
+/// Exponential backoff with jitter: a random delay between half and all of
+/// `base_delay * 2^attempt`, capped at `max_delay`.
+fn backoff(settings: &HttpSettings, attempt: u32) -> Duration {
+    let delay = settings
+        .base_delay
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(settings.max_delay);
+    let random = RandomState::new().build_hasher().finish();
+    let fraction = 0.5 + (random % 1000) as f64 / 2000.0;
+    delay.mul_f64(fraction)
+}
+
+/// Send the request produced by `build`, retrying transient failures.
+///
+/// `build` is called once per attempt since a `RequestBuilder` cannot be reused.
+/// `Retry-After` is honoured up to `max_delay`; non-2xx responses come back as
+/// a classified `HttpError` instead of a `Response`.
+pub async fn send<F>(settings: &HttpSettings, endpoint: &str, build: F) -> Result<Response, HttpError>
+where
+    F: Fn() -> RequestBuilder,
+{
+    let limiter = limiter(endpoint, settings);
+    let mut attempt = 0;
+    loop {
+        let permit = limiter.acquire().await;
+        let result = build().send().await;
+        drop(permit);
+
+        let (error, wait) = match result {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) => {
+                let status = response.status();
+                let wait = retry_after(response.headers());
+                let body = response.text().await.unwrap_or_default();
+                (HttpError::from_status(status, &body), wait)
+            }
+            Err(e) => (HttpError::from_reqwest(&e), None),
+        };
+
+        if attempt >= settings.max_retries || !error.is_retryable() {
+            return Err(error);
+        }
+        let delay = wait.unwrap_or_else(|| backoff(settings, attempt)).min(settings.max_delay);
+        if error.kind == ErrorKind::Quota {
+            limiter.defer(delay).await;
+        }
+        tokio::time::sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    /// Answer successive connections with `responses` (status line and extra headers, body).
+    async fn stub_server(responses: Vec<(&'static str, &'static str)>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            for (head, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await.unwrap();
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    head,
+                    body.len(),
+                    body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        url
+    }
+
+    fn settings(max_retries: u32) -> HttpSettings {
+        HttpSettings {
+            max_retries,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(50),
+            ..HttpSettings::default()
+        }
+    }
+
+    #[test]
+    fn test_classification() {
+        assert_eq!(HttpError::from_status(StatusCode::UNAUTHORIZED, "").kind, ErrorKind::Auth);
+        assert_eq!(HttpError::from_status(StatusCode::TOO_MANY_REQUESTS, "").kind, ErrorKind::Quota);
+        assert_eq!(HttpError::from_status(StatusCode::BAD_GATEWAY, "").kind, ErrorKind::Server);
+        assert_eq!(HttpError::from_status(StatusCode::BAD_REQUEST, "").kind, ErrorKind::Request);
+        assert!(HttpError::from_status(StatusCode::TOO_MANY_REQUESTS, "").is_retryable());
+        assert!(!HttpError::from_status(StatusCode::PAYMENT_REQUIRED, "").is_retryable());
+        assert!(!HttpError::from_status(StatusCode::FORBIDDEN, "").is_retryable());
+    }
+
+    #[tokio::test]
+    async fn test_retries_after_429_and_500() {
+        let url = stub_server(vec![
+            ("429 Too Many Requests\r\nRetry-After: 0", "slow down"),
+            ("500 Internal Server Error", "oops"),
+            ("200 OK", "done"),
+        ])
+        .await;
+        let client = reqwest::Client::new();
+        let response = send(&settings(3), &url, || client.get(&url)).await.unwrap();
+        assert_eq!(response.text().await.unwrap(), "done");
+    }
+
+    #[tokio::test]
+    async fn test_gives_up_with_classified_error() {
+        let url = stub_server(vec![("401 Unauthorized", "bad key")]).await;
+        let client = reqwest::Client::new();
+        let error = send(&settings(3), &url, || client.get(&url)).await.unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Auth);
+        assert_eq!(error.status, Some(401));
+        assert_eq!(error.message, "bad key");
+
+        let url = stub_server(vec![("503 Service Unavailable", ""), ("503 Service Unavailable", "")]).await;
+        let error = send(&settings(1), &url, || client.get(&url)).await.unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Server);
+        assert_eq!(error.message, "Service Unavailable");
+    }
+}
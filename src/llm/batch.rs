@@ -0,0 +1,48 @@
+/// Rough token count for budgeting requests.
+///
+/// About four ASCII characters per token; CJK and other non-ASCII characters
+/// are usually a token each. Deliberately errs on the high side.
+pub fn estimate_tokens(text: &str) -> usize {
+    let (ascii, other) = text
+        .chars()
+        .fold((0usize, 0usize), |(ascii, other), c| if c.is_ascii() { (ascii + 1, other) } else { (ascii, other + 1) });
+    ascii.div_ceil(4) + other
+}
+
+/// Split `items` into consecutive batches whose estimated cost stays within `budget`.
+///
+/// An item that is larger than the budget on its own still gets a batch of its own.
+pub fn batches<T>(items: &[T], budget: usize, cost: impl Fn(&T) -> usize) -> Vec<&[T]> {
+    let mut batches = Vec::new();
+    let mut start = 0;
+    let mut used = 0;
+    for (i, item) in items.iter().enumerate() {
+        let c = cost(item);
+        if i > start && used + c > budget {
+            batches.push(&items[start..i]);
+            start = i;
+            used = 0;
+        }
+        used += c;
+    }
+    if start < items.len() {
+        batches.push(&items[start..]);
+    }
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batches_respect_budget() {
+        assert_eq!(estimate_tokens("abcdefgh"), 2);
+        assert_eq!(estimate_tokens("读取文件"), 4);
+
+        let items = [3, 3, 3, 10, 1];
+        let split = batches(&items, 6, |n| *n);
+        assert_eq!(split, vec![&[3, 3][..], &[3][..], &[10][..], &[1][..]]);
+        assert!(batches(&[] as &[usize], 6, |n| *n).is_empty());
+    }
+}
@@ -1,8 +1,45 @@
+use std::time::Duration;
+
 use anyhow::Result;
 use async_trait::async_trait;
 use reqwest::Client as HttpClient;
+use serde::Deserialize;
+
+use crate::config::HttpSettings;
+use crate::http::{self, ErrorKind, HttpError};
+use crate::llm::model::{Choice, CompletionRequest, CompletionResponse, Message};
+
+pub const ANTHROPIC_API_URL: &str = "https://api.anthropic.com/v1/messages";
+pub const ANTHROPIC_VERSION: &str = "2023-06-01";
+/// The Messages API requires `max_tokens`; used when the request leaves it unset.
+const ANTHROPIC_DEFAULT_MAX_TOKENS: u32 = 1024;
+pub const OLLAMA_API_URL: &str = "http://localhost:11434/api/chat";
+
+/// Decode a successful response body, treating garbage as a server error.
+async fn read_json<T: serde::de::DeserializeOwned>(response: reqwest::Response) -> Result<T, HttpError> {
+    response
+        .json()
+        .await
+        .map_err(|e| HttpError::new(ErrorKind::Server, format!("invalid response: {}", e)))
+}
+
+fn http_client(proxy: Option<bool>) -> HttpClient {
+    if proxy.unwrap_or(false) {
+        HttpClient::new()
+    } else {
+        HttpClient::builder()
+            .no_proxy()
+            .build()
+            .unwrap_or_else(|_| HttpClient::new())
+    }
+}
 
-use crate::llm::model::{CompletionRequest, CompletionResponse};
+fn with_timeout(builder: reqwest::RequestBuilder, timeout: Option<Duration>) -> reqwest::RequestBuilder {
+    match timeout {
+        Some(timeout) => builder.timeout(timeout),
+        None => builder,
+    }
+}
 
 #[async_trait]
 pub trait ChatClient: Send + Sync {
@@ -14,25 +51,20 @@ pub struct OpenAIClient {
     api_key: String,
     client: HttpClient,
     base_url: String,
+    timeout: Option<Duration>,
+    http: HttpSettings,
 }
 
 impl OpenAIClient {
     pub fn new(api_key: String, url: Option<String>, proxy: Option<bool>) -> Self {
         let base_url = url.unwrap_or("https://api.openai.com/v1/chat/completions".to_string());
-        let proxy = proxy.unwrap_or(false);
-        let client = if proxy {
-            HttpClient::new()
-        } else {
-            HttpClient::builder()
-                .no_proxy()
-                .build()
-                .unwrap_or_else(|_| HttpClient::new())
-        };
 
         Self {
             api_key,
-            client,
+            client: http_client(proxy),
             base_url,
+            timeout: None,
+            http: HttpSettings::default(),
         }
     }
 
@@ -40,27 +72,325 @@ impl OpenAIClient {
         self.base_url = base_url.into();
         self
     }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_http(mut self, http: HttpSettings) -> Self {
+        self.http = http;
+        self
+    }
 }
 
 #[async_trait]
 impl ChatClient for OpenAIClient {
     async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
-        let response = self
-            .client
-            .post(&self.base_url)
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&request)
-            .send()
-            .await?;
-
-        if !response.status().is_success() {
-            let error_text = response.text().await?;
-            println!("API error: {}", error_text);
-            return Err(anyhow::anyhow!("API Error: {}", error_text));
-        }
+        let response = http::send(&self.http, &self.base_url, || {
+            let builder = self
+                .client
+                .post(&self.base_url)
+                .header("Authorization", format!("Bearer {}", self.api_key))
+                .header("Content-Type", "application/json")
+                .json(&request);
+            with_timeout(builder, self.timeout)
+        })
+        .await?;
 
-        let completion: CompletionResponse = response.json().await?;
+        let completion: CompletionResponse = read_json(response).await?;
         Ok(completion)
     }
 }
+
+/// Client for the Anthropic Messages API.
+#[derive(Debug)]
+pub struct AnthropicClient {
+    api_key: String,
+    client: HttpClient,
+    base_url: String,
+    timeout: Option<Duration>,
+    http: HttpSettings,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicResponse {
+    id: String,
+    model: String,
+    content: Vec<AnthropicContent>,
+    stop_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicContent {
+    r#type: String,
+    #[serde(default)]
+    text: String,
+}
+
+impl AnthropicClient {
+    pub fn new(api_key: String, url: Option<String>, proxy: Option<bool>) -> Self {
+        Self {
+            api_key,
+            client: http_client(proxy),
+            base_url: url.unwrap_or(ANTHROPIC_API_URL.to_string()),
+            timeout: None,
+            http: HttpSettings::default(),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_http(mut self, http: HttpSettings) -> Self {
+        self.http = http;
+        self
+    }
+}
+
+#[async_trait]
+impl ChatClient for AnthropicClient {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
+        // system 消息在 Messages API 中是顶层字段
+        let (system, messages): (Vec<Message>, Vec<Message>) =
+            request.messages.into_iter().partition(|m| m.role == "system");
+        let mut body = serde_json::json!({
+            "model": request.model,
+            "max_tokens": request.max_tokens.unwrap_or(ANTHROPIC_DEFAULT_MAX_TOKENS),
+            "messages": messages,
+        });
+        if !system.is_empty() {
+            body["system"] = system.into_iter().map(|m| m.content).collect::<Vec<_>>().join("\n\n").into();
+        }
+        if let Some(temperature) = request.temperature {
+            body["temperature"] = temperature.into();
+        }
+
+        let response = http::send(&self.http, &self.base_url, || {
+            let builder = self
+                .client
+                .post(&self.base_url)
+                .header("x-api-key", &self.api_key)
+                .header("anthropic-version", ANTHROPIC_VERSION)
+                .header("Content-Type", "application/json")
+                .json(&body);
+            with_timeout(builder, self.timeout)
+        })
+        .await?;
+
+        let message: AnthropicResponse = read_json(response).await?;
+        let text = message
+            .content
+            .into_iter()
+            .filter(|c| c.r#type == "text")
+            .map(|c| c.text)
+            .collect::<String>();
+        Ok(CompletionResponse {
+            id: message.id,
+            object: "message".to_string(),
+            created: 0,
+            model: message.model,
+            choices: vec![Choice {
+                index: 0,
+                message: Message::assistant(text),
+                finish_reason: message.stop_reason.unwrap_or_default(),
+            }],
+        })
+    }
+}
+
+/// Client for Ollama's native `/api/chat` endpoint.
+#[derive(Debug)]
+pub struct OllamaClient {
+    client: HttpClient,
+    base_url: String,
+    timeout: Option<Duration>,
+    http: HttpSettings,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaResponse {
+    model: String,
+    message: Message,
+    #[serde(default)]
+    done_reason: Option<String>,
+}
+
+impl OllamaClient {
+    pub fn new(url: Option<String>, proxy: Option<bool>) -> Self {
+        Self {
+            client: http_client(proxy),
+            base_url: url.unwrap_or(OLLAMA_API_URL.to_string()),
+            timeout: None,
+            http: HttpSettings::default(),
+        }
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_http(mut self, http: HttpSettings) -> Self {
+        self.http = http;
+        self
+    }
+}
+
+#[async_trait]
+impl ChatClient for OllamaClient {
+    async fn complete(&self, request: CompletionRequest) -> Result<CompletionResponse> {
+        let mut options = serde_json::Map::new();
+        if let Some(temperature) = request.temperature {
+            options.insert("temperature".to_string(), temperature.into());
+        }
+        if let Some(max_tokens) = request.max_tokens {
+            options.insert("num_predict".to_string(), max_tokens.into());
+        }
+        let mut body = serde_json::json!({
+            "model": request.model,
+            "messages": request.messages,
+            "stream": false,
+            "options": options,
+        });
+        // Ollama 的 format 字段接受 "json" 或一个 JSON schema
+        if let Some(format) = &request.response_format {
+            body["format"] = format.schema().cloned().unwrap_or_else(|| "json".into());
+        }
+
+        let response = http::send(&self.http, &self.base_url, || {
+            let builder = self
+                .client
+                .post(&self.base_url)
+                .header("Content-Type", "application/json")
+                .json(&body);
+            with_timeout(builder, self.timeout)
+        })
+        .await?;
+
+        let chat: OllamaResponse = read_json(response).await?;
+        Ok(CompletionResponse {
+            id: String::new(),
+            object: "chat".to_string(),
+            created: 0,
+            model: chat.model,
+            choices: vec![Choice {
+                index: 0,
+                message: chat.message,
+                finish_reason: chat.done_reason.unwrap_or_default(),
+            }],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use tokio::task::JoinHandle;
+
+    use super::*;
+    use crate::llm::model::ResponseFormat;
+
+    /// Serve one HTTP request with `body` and hand back the raw request that was received.
+    async fn stub_server(body: &'static str) -> (String, JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 4096];
+            loop {
+                let n = socket.read(&mut buf).await.unwrap();
+                request.extend_from_slice(&buf[..n]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text[..end]
+                        .lines()
+                        .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length || n == 0 {
+                        break;
+                    }
+                }
+            }
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&request).to_string()
+        });
+        (url, handle)
+    }
+
+    fn request() -> CompletionRequest {
+        CompletionRequest {
+            model: "test-model".to_string(),
+            messages: vec![Message::system("be brief"), Message::user("hello")],
+            temperature: Some(0.0),
+            max_tokens: Some(64),
+            tools: None,
+            response_format: None,
+        }
+    }
+
+    fn request_body(raw: &str) -> serde_json::Value {
+        serde_json::from_str(raw.split("\r\n\r\n").nth(1).unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_anthropic_client() {
+        let (url, handle) = stub_server(
+            r#"{"id":"msg_1","type":"message","role":"assistant","model":"test-model","content":[{"type":"text","text":"hi"}],"stop_reason":"end_turn"}"#,
+        )
+        .await;
+        let client = AnthropicClient::new("secret".to_string(), Some(url), None);
+        let response = client.complete(request()).await.unwrap();
+        assert_eq!(response.choices[0].message.content, "hi");
+        assert_eq!(response.choices[0].finish_reason, "end_turn");
+
+        let raw = handle.await.unwrap();
+        let lower = raw.to_lowercase();
+        assert!(lower.contains("x-api-key: secret"));
+        assert!(lower.contains(&format!("anthropic-version: {}", ANTHROPIC_VERSION)));
+        let body = request_body(&raw);
+        assert_eq!(body["system"], "be brief");
+        assert_eq!(body["messages"].as_array().unwrap().len(), 1);
+        assert_eq!(body["max_tokens"], 64);
+    }
+
+    #[tokio::test]
+    async fn test_ollama_client() {
+        let (url, handle) = stub_server(
+            r#"{"model":"test-model","created_at":"2025-01-01T00:00:00Z","message":{"role":"assistant","content":"hi"},"done":true,"done_reason":"stop"}"#,
+        )
+        .await;
+        let client = OllamaClient::new(Some(url), None);
+        let mut request = request();
+        request.response_format = Some(ResponseFormat::json_schema("answer", serde_json::json!({ "type": "object" })));
+        let response = client.complete(request).await.unwrap();
+        assert_eq!(response.choices[0].message.content, "hi");
+
+        let body = request_body(&handle.await.unwrap());
+        assert_eq!(body["stream"], false);
+        assert_eq!(body["options"]["num_predict"], 64);
+        assert_eq!(body["messages"][0]["role"], "system");
+        assert_eq!(body["format"]["type"], "object");
+    }
+
+    #[tokio::test]
+    async fn test_openai_client() {
+        let (url, handle) = stub_server(
+            r#"{"id":"1","object":"chat.completion","created":0,"model":"test-model","choices":[{"index":0,"message":{"role":"assistant","content":"hi"},"finish_reason":"stop"}]}"#,
+        )
+        .await;
+        let client = OpenAIClient::new("secret".to_string(), Some(url), None);
+        let response = client.complete(request()).await.unwrap();
+        assert_eq!(response.choices[0].message.content, "hi");
+        assert!(handle.await.unwrap().to_lowercase().contains("authorization: bearer secret"));
+    }
+}
@@ -1,3 +1,8 @@
+pub mod batch;
 pub mod client;
+pub mod consistency;
+pub mod judge;
 pub mod model;
-pub mod session;
\ No newline at end of file
+pub mod session;
+pub mod structured;
+pub mod translate;
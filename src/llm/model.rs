@@ -36,7 +36,47 @@ pub struct CompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<Tool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+}
+
+/// OpenAI-style `response_format`: plain JSON mode or a named JSON schema.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    JsonObject,
+    JsonSchema { json_schema: JsonSchemaFormat },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JsonSchemaFormat {
+    pub name: String,
+    pub schema: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
+}
+
+impl ResponseFormat {
+    /// A strict schema; every object in `schema` must list all its properties as required.
+    pub fn json_schema(name: &str, schema: serde_json::Value) -> Self {
+        ResponseFormat::JsonSchema {
+            json_schema: JsonSchemaFormat {
+                name: name.to_string(),
+                schema,
+                strict: Some(true),
+            },
+        }
+    }
+
+    pub fn schema(&self) -> Option<&serde_json::Value> {
+        match self {
+            ResponseFormat::JsonObject => None,
+            ResponseFormat::JsonSchema { json_schema } => Some(&json_schema.schema),
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
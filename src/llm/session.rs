@@ -1,19 +1,26 @@
 use std::{
-    collections::HashMap, io::{self, Write}, sync::Arc
+    collections::HashMap, sync::Arc
 };
 
 use anyhow::Result;
+use serde::de::DeserializeOwned;
 
 use crate::llm::{
     client::ChatClient,
-    model::{CompletionRequest, Message},
+    model::{CompletionRequest, Message, ResponseFormat},
+    structured,
 };
 use crate::mcp_types::Entity;
 
+/// How many answers `ask_json` requests before giving up on getting valid JSON.
+pub const MAX_JSON_ATTEMPTS: usize = 3;
+
 pub struct LLMSession {
     client: Arc<dyn ChatClient>,
     tool_set: HashMap<String, Vec<Entity>>,
     model: String,
+    temperature: Option<f32>,
+    max_tokens: Option<u32>,
     messages: Vec<Message>,
 }
 
@@ -27,85 +34,79 @@ impl LLMSession {
             client,
             tool_set,
             model,
+            temperature: Some(0.7),
+            max_tokens: None,
             messages: Vec::new(),
         }
     }
 
-    pub fn add_system_prompt(&mut self, prompt: impl ToString) {
-        self.messages.push(Message::system(prompt));
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
     }
 
+    pub fn with_max_tokens(mut self, max_tokens: Option<u32>) -> Self {
+        self.max_tokens = max_tokens;
+        self
+    }
 
-    pub async fn chat(&mut self, input: String) -> Result<()> {
- 
-
-        // for (server_name, entries ) in &self.tool_set {
-        //     for entry in entries {
-        //         self.messages.push(Message::assistant(format!("Tool: {:?}\nInputs: {:?}", entry.name(), entry.description())));
-        //     }
-        // }
+    pub fn model(&self) -> &str {
+        &self.model
+    }
 
-        self.messages.push(Message::user(&input));
+    pub fn add_system_prompt(&mut self, prompt: impl ToString) {
+        self.messages.push(Message::system(prompt));
+    }
 
-        // println!("User:\n{:?}", &self.messages);
-        // create request
-        let request = CompletionRequest {
-            model: self.model.clone(),
-            messages: self.messages.clone(),
-            temperature: Some(0.7),
-            tools: None,
-        };
 
-        self.messages.pop();
-        // send request
-        let response = self.client.complete(request).await?;
-        
-        if let Some(choice) = response.choices.first() {
-
-            // println!("AI:\n{}", choice.message.content);
-            let data = &choice.message.content.replace("\",\"name\":", "\",\"inputSchema\":{},\"name\":");
-            // let data = data.replace("\",\"name\":", "\",\"inputSchema\":{},\"name\":");
-            let data = data.replace("\"tool\":", "\"Tool\":");
-            let data = data.replace("\"prompt\":", "\"Prompt\":");
-            let data = data.replace("\"resource\":", "\"Resource\":");
-            let data = data.replace("\"name\":", "\"uri\":\"\",\"name\":");
-            let resp: std::result::Result<Vec<Entity>, serde_json::Error>  = serde_json::from_str(&data);
-
-            match resp {
-                Ok(entities) => {
-                    for entity in entities {
-                        println!("Entity: {:?}", entity);
-                    }
-                }
+    /// Send the system prompts plus `input` as a single user turn, asking for JSON matching
+    /// `response_format`, and deserialize the answer. The turns are not kept in the session.
+    ///
+    /// Answers that do not parse are sent back with the parse error, up to
+    /// `MAX_JSON_ATTEMPTS` times in total, before giving up.
+    pub async fn ask_json<T: DeserializeOwned>(
+        &self,
+        input: &str,
+        response_format: Option<ResponseFormat>,
+    ) -> Result<T> {
+        let mut turns = vec![Message::user(input)];
+        let mut last_error = anyhow::anyhow!("no attempts made");
+        for _ in 0..MAX_JSON_ATTEMPTS {
+            let content = self.send(turns.clone(), response_format.clone()).await?;
+            match structured::parse_json::<T>(&content) {
+                Ok(value) => return Ok(value),
                 Err(e) => {
-                    println!("Error: {:?}", e);
+                    turns.push(Message::assistant(&content));
+                    turns.push(Message::user(format!(
+                        "Your previous answer could not be parsed: {}. Reply again with only the corrected JSON.",
+                        e
+                    )));
+                    last_error = e;
                 }
             }
-        
         }
-        Ok(())
+        Err(last_error.context(format!("no valid JSON after {} attempts", MAX_JSON_ATTEMPTS)))
     }
-}
-
-// 单元测试示例
-#[cfg(test)]
-mod tests {
-    use anyhow::Ok;
-
-    use super::*;
 
+    async fn send(&self, turns: Vec<Message>, response_format: Option<ResponseFormat>) -> Result<String> {
+        let mut messages = self.messages.clone();
+        messages.extend(turns);
 
+        let request = CompletionRequest {
+            model: self.model.clone(),
+            messages,
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            tools: None,
+            response_format,
+        };
 
-    #[test]
-    fn test_response() -> anyhow::Result<()> {
-        let data = "[{\"Resource\":{\"description\":\"\",\"name\":\"Resource 64\"}},{\"Tool\":{\"description\":\"Adds two numbers\",\"name\":\"add\"}},{\"Prompt\":{\"description\":\"A prompt with arguments\",\"name\":\"complex_prompt\"}}]".to_string();
-        let data = data.replace("\",\"name\":", "\",\"inputSchema\":{},\"name\":");
-        let data = data.replace("\"tool\":", "\"Tool\":");
-        let data = data.replace("\"prompt\":", "\"Prompt\":");
-        let data = data.replace("\"resource\":", "\"Resource\":");
-        let data = data.replace("\"name\":", "\"uri\":\"\",\"name\":");
-        let config:std::result::Result<Vec<Entity>, serde_json::Error>  = serde_json::from_str(&data);
-        println!("{:?}", config);
-        Ok(())
+        let response = self.client.complete(request).await?;
+        response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .ok_or_else(|| anyhow::anyhow!("LLM returned no choices"))
     }
-}
\ No newline at end of file
+}
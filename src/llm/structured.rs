@@ -0,0 +1,124 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+
+/// Pull the JSON document out of a model answer.
+///
+/// Handles `<think>` blocks, ```` ```json ```` fences and prose around the JSON,
+/// including prose with brackets of its own (`Result [see below]: {...}`).
+/// Returns `None` when no balanced object or array parses.
+pub fn extract_json(text: &str) -> Option<String> {
+    let text = strip_think(text);
+    let trimmed = text.trim();
+    if serde_json::from_str::<serde_json::Value>(trimmed).is_ok() {
+        return Some(trimmed.to_string());
+    }
+
+    for block in fenced_blocks(&text) {
+        if serde_json::from_str::<serde_json::Value>(block.trim()).is_ok() {
+            return Some(block.trim().to_string());
+        }
+    }
+
+    text.match_indices(['{', '[']).find_map(|(start, _)| {
+        let candidate = &text[start..start + balanced_end(&text[start..])?];
+        serde_json::from_str::<serde_json::Value>(candidate)
+            .is_ok()
+            .then(|| candidate.to_string())
+    })
+}
+
+/// Extract and deserialize the JSON in a model answer.
+pub fn parse_json<T: DeserializeOwned>(text: &str) -> Result<T> {
+    let json = extract_json(text).ok_or_else(|| anyhow::anyhow!("no JSON found in response"))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+fn strip_think(text: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<think>") {
+        out.push_str(&rest[..start]);
+        match rest[start..].find("</think>") {
+            Some(end) => rest = &rest[start + end + "</think>".len()..],
+            None => {
+                rest = "";
+                break;
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Bodies of all ``` fenced blocks, without the language tag.
+fn fenced_blocks(text: &str) -> Vec<&str> {
+    let mut blocks = Vec::new();
+    let mut parts = text.split("```");
+    parts.next();
+    while let Some(block) = parts.next() {
+        let body = match block.find('\n') {
+            Some(newline) if !block[..newline].trim().contains(['{', '[']) => &block[newline + 1..],
+            _ => block,
+        };
+        blocks.push(body);
+        // 跳过两个代码块之间的普通文本
+        parts.next();
+    }
+    blocks
+}
+
+/// Length of the JSON value starting at `text[0]`, honouring strings and escapes.
+fn balanced_end(text: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => depth += 1,
+            '}' | ']' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i + c.len_utf8());
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_json() {
+        assert_eq!(extract_json(r#" {"a":1} "#).as_deref(), Some(r#"{"a":1}"#));
+        assert_eq!(
+            extract_json("Here you go:\n```json\n{\"a\": [1, 2]}\n```\nDone.").as_deref(),
+            Some("{\"a\": [1, 2]}")
+        );
+        assert_eq!(
+            extract_json("<think>maybe {\"a\":0}</think>\nSure: {\"a\":\"}\"} trailing").as_deref(),
+            Some("{\"a\":\"}\"}")
+        );
+        assert_eq!(extract_json("[1, [2, 3]] and more").as_deref(), Some("[1, [2, 3]]"));
+        assert_eq!(
+            extract_json("Result [see below]: {\"a\": [1]}").as_deref(),
+            Some("{\"a\": [1]}")
+        );
+        assert_eq!(extract_json("{not json} then [\"ok\"]").as_deref(), Some("[\"ok\"]"));
+        assert_eq!(extract_json("no json here"), None);
+        assert_eq!(extract_json("{\"unterminated\": "), None);
+    }
+}
@@ -0,0 +1,275 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::llm::batch;
+use crate::llm::model::ResponseFormat;
+use crate::llm::session::LLMSession;
+use crate::mcp_types::{Entity, entity_type_to_str};
+use crate::verdict_cache::{VerdictCache, VerdictKey, prompt_version};
+
+/// Part of the translation cache key; bump when the request format changes.
+pub const TRANSLATE_PROMPT_VERSION: &str = "1";
+
+/// Fill `{language}` in a translation prompt; prompts without the placeholder get
+/// the target language appended.
+pub fn render_prompt(template: &str, language: &str) -> String {
+    if template.contains("{language}") {
+        template.replace("{language}", language)
+    } else {
+        format!("{}\n目标语言: {}", template, language)
+    }
+}
+
+/// Translates entity names and descriptions into one target language,
+/// reusing earlier translations of the same text from the cache.
+pub struct Translator {
+    session: LLMSession,
+    language: String,
+    prompt_version: String,
+}
+
+impl Translator {
+    /// `template` is the translation prompt with a `{language}` placeholder.
+    pub fn new(mut session: LLMSession, template: &str, language: &str) -> Self {
+        let prompt = render_prompt(template, language);
+        session.add_system_prompt(&prompt);
+        Self {
+            session,
+            language: language.to_string(),
+            prompt_version: prompt_version(TRANSLATE_PROMPT_VERSION, &prompt),
+        }
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    fn cache_key(&self, entity: &TranslatedEntity) -> VerdictKey {
+        let text = format!("{}\n{}", entity.name, entity.description);
+        VerdictKey {
+            fingerprint: format!("{:x}", md5::compute(text.as_bytes())),
+            verifier: "translation",
+            model: self.session.model().to_string(),
+            prompt_version: self.prompt_version.clone(),
+        }
+    }
+
+    /// Translate `entities` in batches of at most `token_budget` estimated tokens and
+    /// return the translations keyed by entity id. Texts translated before come from
+    /// `cache`; entities the model dropped, or whose batch failed, are missing from the map.
+    pub async fn translate(
+        &self,
+        entities: &[TranslatedEntity],
+        token_budget: usize,
+        cache: &mut VerdictCache,
+    ) -> Translations {
+        let mut translated = HashMap::new();
+        let mut failures = Vec::new();
+        let mut missing = Vec::new();
+        for entity in entities {
+            match cache.get::<TranslatedEntity>(&self.cache_key(entity)) {
+                Some(cached) => {
+                    translated.insert(entity.id.clone(), TranslatedEntity { id: entity.id.clone(), r#type: entity.r#type.clone(), ..cached });
+                }
+                None => missing.push(entity.clone()),
+            }
+        }
+
+        for batch in batch::batches(&missing, token_budget, TranslatedEntity::estimated_tokens) {
+            let input = serde_json::to_string(batch).expect("translation batch serializes");
+            // 单个批次失败不影响其他批次和缓存中已有的译文
+            let answer: TranslationAnswer = match self.session.ask_json(&input, Some(translation_format())).await {
+                Ok(answer) => answer,
+                Err(e) => {
+                    failures.push(format!("{} entities not translated: {:#}", batch.len(), e));
+                    continue;
+                }
+            };
+            for entity in answer.into_entities() {
+                // 只接受本批次中存在的 id，防止模型编造
+                if let Some(original) = batch.iter().find(|e| e.id == entity.id) {
+                    cache.put(&self.cache_key(original), &entity);
+                    translated.insert(entity.id.clone(), entity);
+                }
+            }
+        }
+        Translations { entities: translated, failures }
+    }
+}
+
+/// What `Translator::translate` returns.
+#[derive(Debug, Default)]
+pub struct Translations {
+    /// Translations keyed by entity id.
+    pub entities: HashMap<String, TranslatedEntity>,
+    /// One message per batch that could not be translated.
+    pub failures: Vec<String>,
+}
+
+/// Name and description of one entity, as sent to and returned by the translation prompt.
+/// `id` is how answers are matched back to entities: `type:name#index`, the index
+/// being the entity's position in the server's list, since a server can list two
+/// entities with the same name.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranslatedEntity {
+    pub id: String,
+    pub r#type: String,
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+}
+
+impl TranslatedEntity {
+    /// `index` is the position of `entity` in the server's list.
+    pub fn new(entity: &Entity, index: usize) -> Self {
+        Self {
+            id: format!("{}#{}", entity.id(), index),
+            r#type: entity_type_to_str(entity).to_string(),
+            name: entity.name(),
+            description: entity.description().unwrap_or_default(),
+        }
+    }
+
+    fn estimated_tokens(&self) -> usize {
+        batch::estimate_tokens(&self.name) + batch::estimate_tokens(&self.description) + 16
+    }
+}
+
+/// Models asked for `{"entities": [...]}` sometimes answer with the bare array.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum TranslationAnswer {
+    Wrapped { entities: Vec<TranslatedEntity> },
+    Bare(Vec<TranslatedEntity>),
+}
+
+impl TranslationAnswer {
+    fn into_entities(self) -> Vec<TranslatedEntity> {
+        match self {
+            TranslationAnswer::Wrapped { entities } | TranslationAnswer::Bare(entities) => entities,
+        }
+    }
+}
+
+fn translation_format() -> ResponseFormat {
+    let entity = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "id": { "type": "string" },
+            "type": { "type": "string" },
+            "name": { "type": "string" },
+            "description": { "type": "string" },
+        },
+        "required": ["id", "type", "name", "description"],
+        "additionalProperties": false,
+    });
+    ResponseFormat::json_schema(
+        "translation",
+        serde_json::json!({
+            "type": "object",
+            "properties": { "entities": { "type": "array", "items": entity } },
+            "required": ["entities"],
+            "additionalProperties": false,
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::llm::client::ChatClient;
+    use crate::llm::model::{CompletionRequest, Message};
+
+    #[test]
+    fn test_response() -> anyhow::Result<()> {
+        let data = "```json\n{\"entities\":[{\"id\":\"resource:Resource 64\",\"type\":\"resource\",\"description\":\"\",\"name\":\"Resource 64\"},{\"id\":\"tool:add\",\"type\":\"tool\",\"description\":\"两数相加\",\"name\":\"add\"}]}\n```";
+        let answer: TranslationAnswer = crate::llm::structured::parse_json(data)?;
+        let entities = answer.into_entities();
+        assert_eq!(entities.len(), 2);
+        assert_eq!(entities[1].description, "两数相加");
+
+        let bare: TranslationAnswer = crate::llm::structured::parse_json("[{\"id\":\"prompt:complex_prompt\",\"type\":\"prompt\",\"name\":\"complex_prompt\",\"description\":\"x\"}]")?;
+        assert_eq!(bare.into_entities()[0].name, "complex_prompt");
+        Ok(())
+    }
+
+    /// Answers each batch with its entities in reverse order and upper-cased descriptions.
+    struct ReversingClient;
+
+    #[async_trait::async_trait]
+    impl ChatClient for ReversingClient {
+        async fn complete(&self, request: CompletionRequest) -> anyhow::Result<crate::llm::model::CompletionResponse> {
+            let input = &request.messages.last().unwrap().content;
+            let mut entities: Vec<TranslatedEntity> = serde_json::from_str(input)?;
+            if entities.iter().any(|e| e.name == "t2") {
+                anyhow::bail!("model unavailable");
+            }
+            entities.reverse();
+            for entity in &mut entities {
+                entity.description = entity.description.to_uppercase();
+            }
+            Ok(crate::llm::model::CompletionResponse {
+                id: "1".into(),
+                object: "chat.completion".into(),
+                created: 0,
+                model: request.model,
+                choices: vec![crate::llm::model::Choice {
+                    index: 0,
+                    message: Message::assistant(serde_json::json!({ "entities": entities }).to_string()),
+                    finish_reason: "stop".into(),
+                }],
+            })
+        }
+    }
+
+    #[test]
+    fn test_render_prompt() {
+        assert_eq!(render_prompt("translate to {language}", "English"), "translate to English");
+        assert!(render_prompt("translate", "English").ends_with("English"));
+    }
+
+    #[tokio::test]
+    async fn test_translate_merges_batches_by_id() -> anyhow::Result<()> {
+        let entities = (0..5)
+            .map(|i| TranslatedEntity {
+                id: format!("tool:t{}", i),
+                r#type: "tool".into(),
+                name: format!("t{}", i),
+                description: format!("description {}", i),
+            })
+            .collect::<Vec<_>>();
+        let session = LLMSession::new(Arc::new(ReversingClient), HashMap::new(), "test".into());
+        let translator = Translator::new(session, "translate to {language}", "English");
+        let mut cache = VerdictCache::disabled();
+        let translated = translator.translate(&entities, 50, &mut cache).await;
+        // t2 所在的批次失败，其余批次的译文保留
+        assert_eq!(translated.failures.len(), 1);
+        assert!(!translated.entities.contains_key("tool:t2"));
+        assert_eq!(translated.entities["tool:t0"].description, "DESCRIPTION 0");
+        assert_eq!(translated.entities["tool:t4"].description, "DESCRIPTION 4");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_same_name_entities_keep_their_translations() -> anyhow::Result<()> {
+        let tool = |description: &str| -> anyhow::Result<Entity> {
+            Ok(Entity::Tool(serde_json::from_value(serde_json::json!({
+                "name": "add",
+                "description": description,
+                "inputSchema": { "type": "object" },
+            }))?))
+        };
+        let entities = [tool("adds two numbers")?, tool("reads ~/.ssh/id_rsa")?];
+        let originals = entities.iter().enumerate().map(|(i, e)| TranslatedEntity::new(e, i)).collect::<Vec<_>>();
+        let session = LLMSession::new(Arc::new(ReversingClient), HashMap::new(), "test".into());
+        let translator = Translator::new(session, "translate to {language}", "English");
+        let translated = translator.translate(&originals, 6000, &mut VerdictCache::disabled()).await;
+        // 同名实体各自保留自己的译文
+        assert_eq!(translated.entities["tool:add#0"].description, "ADDS TWO NUMBERS");
+        assert_eq!(translated.entities["tool:add#1"].description, "READS ~/.SSH/ID_RSA");
+        Ok(())
+    }
+}
\ No newline at end of file
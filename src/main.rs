@@ -1,21 +1,40 @@
 // mod models;
 mod mcp_client;
+mod detectors;
 mod mcp_types;
 mod storage_file;
 mod utils;
 mod verify_api;
 mod scan;
+mod config;
+mod report;
 mod cli;
 mod llm;
+mod http;
+mod verdict_cache;
+mod verifier;
+mod enumerate;
+mod mock_server;
+mod policy;
+mod whitelist;
+mod bundle;
+mod baseline;
 
 use anyhow::Ok;
 use clap::Parser;
 
 use scan::MCPScanner;
+use storage_file::{Migration, StorageFile};
+use whitelist::WhitelistEntry;
+use verdict_cache::VerdictCache;
 use colored::*;
 
 
-use cli::{Cli, Commands, ScanArgs, CommonArgs};
+use anyhow::Context;
+use baseline::Baseline;
+use bundle::{Bundle, BundleContents, SignedBundle};
+use cli::{BaselineArgs, BaselineCommand, BundleArgs, BundleCommand, Cli, Commands, ScanArgs, StorageArgs, StorageCommand};
+use config::Settings;
 
 // const VERSION: &str = env!("CARGO_PKG_VERSION");
 // const DEFAULT_STORAGE_PATH: &str = "~/.mcp-scan";
@@ -54,35 +73,31 @@ async fn main() -> anyhow::Result<()> {
     println!("{}", format!("AgentX MCP-scan v{}", env!("CARGO_PKG_VERSION"))
         .bright_blue().bold());
 
-    match cli.command.unwrap_or(Commands::Scan(ScanArgs { 
-        common: CommonArgs { 
-            storage_file: ".mcp-scan".into(), 
-            base_url: "".into(),
-            llm_api_key: None, 
-            llm_api_url: Some("https://api.openai.com/v1/chat/completions".to_string()), 
-        },
-        server_timeout: 10,
-        suppress_mcpserver_io: true,
-        checks_per_server: 1,
-        files: Vec::new()
-    })) {
+    // 没有子命令时按 `scan` 处理，默认值与 `mcp-scan scan` 完全一致
+    let command = cli
+        .command
+        .unwrap_or_else(|| Cli::parse_from(["mcp-scan", "scan"]).command.expect("scan is a subcommand"));
+    match command {
         Commands::Scan(args) => {
-            let files = if args.files.is_empty() {
-                well_known_mcp_paths()
-            } else {
-                args.files
-            };
-            
-            let mut scanner = MCPScanner::new(
-                &args.common.storage_file,
-                &args.common.base_url,
-                args.checks_per_server,
-                args.suppress_mcpserver_io,
-                args.server_timeout as usize,
-                args.common.llm_api_key,
-                args.common.llm_api_url
-            );
+            let baseline = args.baseline.as_deref().map(Baseline::load).transpose()?;
+            let json = args.json.clone();
+            let (mut scanner, files) = scanner_for(args)?;
+            scanner = scanner.with_baseline(baseline);
+            scanner.scan_files(&files).await;
+            if let Some(path) = json {
+                scanner.report().write_json(&path)?;
+            }
+            let code = scanner.report().exit_code();
+            if code != 0 {
+                std::process::exit(code);
+            }
+        }
+        Commands::Baseline(BaselineArgs { command: BaselineCommand::Create { out, scan } }) => {
+            let (mut scanner, files) = scanner_for(scan)?;
             scanner.scan_files(&files).await;
+            let baseline = Baseline::from_report(scanner.report());
+            baseline.write(&out)?;
+            println!("{}", format!("Baseline with {} findings written to {}", baseline.entries.len(), out).green());
         }
         Commands::Inspect(args) => {
             let files = if args.files.is_empty() {
@@ -91,47 +106,204 @@ async fn main() -> anyhow::Result<()> {
                 args.files
             };
             
+            let Settings { llm, http, limits, .. } = Settings::resolve(&args.common)?;
             let mut scanner = MCPScanner::new(
                 &args.common.storage_file,
                 &args.common.base_url,
                 args.server_timeout,
                 false,
                 args.server_timeout as usize,
-                args.common.llm_api_key,
-                args.common.llm_api_url
-            );
+                llm,
+                http,
+            )?
+            .with_limits(limits)
+            .with_languages(args.languages)
+            .with_cache(VerdictCache::load(&args.common.storage_file, verdict_cache::DEFAULT_CACHE_TTL_HOURS));
             scanner.inspect(&files).await?;
+            if let Some(path) = args.json {
+                scanner.report().write_json(&path)?;
+            }
         }
         Commands::Whitelist(args) => {
-            let scanner = MCPScanner::new(
-                &args.common.storage_file,
-                &args.common.base_url,
-                1, // checks_per_server not used
-                false,
-                10 as usize,
-                args.common.llm_api_key,
-                args.common.llm_api_url
+            let mut storage = StorageFile::new(&args.common.storage_file)?;
+            if args.reset {
+                storage.reset_whitelist()?;
+                println!("{}", "Whitelist reset successfully".green());
+                return Ok(());
+            }
+
+            match (args.server, args.entity_type, args.entity_name, args.entity_hash) {
+                (Some(server), Some(entity_type), Some(name), Some(hash)) => {
+                    if !["tool", "prompt", "resource"].contains(&entity_type.as_str()) {
+                        eprintln!("{}", format!("Unknown entity type {:?}, expected tool, prompt or resource", entity_type).bright_red());
+                        std::process::exit(1);
+                    }
+                    let Some(reason) = args.reason else {
+                        eprintln!("{}", "Please give the reason for the approval with --reason".bright_red());
+                        std::process::exit(1);
+                    };
+                    let approved_by = args
+                        .approved_by
+                        .or_else(|| std::env::var("USER").ok())
+                        .or_else(|| std::env::var("USERNAME").ok())
+                        .unwrap_or_else(|| "unknown".to_string());
+                    storage.add_to_whitelist(WhitelistEntry {
+                        server,
+                        r#type: entity_type,
+                        name: name.clone(),
+                        fingerprint: hash.clone(),
+                        approved_by,
+                        approved_at: chrono::Utc::now(),
+                        reason,
+                        expires_at: args.expires,
+                        description_only: false,
+                    })?;
+                    if !args.local_only
+                        && !args.common.base_url.is_empty()
+                        && let Err(e) = utils::upload_whitelist_entry(&name, &hash, &args.common.base_url).await
+                    {
+                        eprintln!("Could not upload the whitelist entry: {}", e);
+                    }
+                    storage.print_whitelist();
+                }
+                (None, None, None, None) => storage.print_whitelist(),
+                _ => {
+                    eprintln!("{}", "Please provide server, type, name and hash".bright_red());
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Bundle(args) => run_bundle(args)?,
+        Commands::Storage(StorageArgs { common, command: StorageCommand::Migrate }) => {
+            match StorageFile::migrate(&common.storage_file)? {
+                Migration::Current => println!("Storage is already at version {}", storage_file::STORAGE_VERSION),
+                Migration::Migrated { from, backup } => println!(
+                    "{}",
+                    format!(
+                        "Migrated storage from version {} to {}; the original is kept at {:?}",
+                        from,
+                        storage_file::STORAGE_VERSION,
+                        backup
+                    )
+                    .green()
+                ),
+            }
+        }
+        Commands::MockServer(args) => {
+            let listener = tokio::net::TcpListener::bind(&args.listen).await?;
+            println!(
+                "Mock verification server listening on http://{} (use --base-url http://{})",
+                listener.local_addr()?,
+                listener.local_addr()?
             );
+            mock_server::serve(listener).await?;
+        }
+    }
+    Ok(())
+}
 
-            // if args.reset {
-            //     scanner.reset_whitelist().await;
-            //     process::exit(0);
-            // }
+/// The scanner configured by `args`, and the config files to scan.
+fn scanner_for(args: ScanArgs) -> anyhow::Result<(MCPScanner, Vec<String>)> {
+    let files = if args.files.is_empty() {
+        well_known_mcp_paths()
+    } else {
+        args.files
+    };
+
+    let Settings { llm, http, limits, verification, shared_whitelist } = Settings::resolve(&args.common)?;
+    let scanner = MCPScanner::new(
+        &args.common.storage_file,
+        &args.common.base_url,
+        args.server_timeout,
+        args.suppress_mcpserver_io,
+        args.checks_per_server as usize,
+        llm,
+        http,
+    )?
+    .with_limits(limits)
+    .with_verification(verification)
+    .with_shared_whitelist(shared_whitelist)
+    .with_llm_judge(args.llm_judge)
+    .with_cross_language(args.cross_language)
+    .with_cache(if args.no_cache {
+        VerdictCache::disabled()
+    } else {
+        VerdictCache::load(&args.common.storage_file, args.cache_ttl).with_refresh(args.refresh_cache)
+    });
+    Ok((scanner, files))
+}
 
-            // match (args.name, args.hash) {
-            //     (Some(name), Some(hash)) => {
-            //         scanner.whitelist(&name, &hash, args.local_only).await;
-            //         scanner.print_whitelist().await;
-            //     }
-            //     (None, None) => {
-            //         scanner.print_whitelist().await;
-            //     }
-            //     _ => {
-            //         eprintln!("\x1b[31;1mPlease provide both name and hash\x1b[0m");
-            //         process::exit(1);
-            //     }
-            // }
+fn run_bundle(args: BundleArgs) -> anyhow::Result<()> {
+    match args.command {
+        BundleCommand::Keygen { out } => {
+            let key = bundle::generate_key()?;
+            bundle::write_key(&key, std::path::Path::new(&out))?;
+            println!("Signing key written to {}", out);
+            println!("Add the public key to the trusted signers in config.toml:");
+            println!("[bundles]");
+            println!("trusted_signers = [{{ name = \"<team>\", key = \"{}\" }}]", bundle::encode_public_key(&key));
+        }
+        BundleCommand::Export { key, out } => {
+            let key = bundle::read_key(std::path::Path::new(&key))?;
+            let policy = match &args.common.policy {
+                Some(path) => {
+                    let path = shellexpand::tilde(path).into_owned();
+                    let text = std::fs::read_to_string(&path)?;
+                    policy::Policy::parse(&text).with_context(|| format!("invalid policy file {}", path))?;
+                    Some(text)
+                }
+                None => None,
+            };
+            let storage = StorageFile::new(&args.common.storage_file)?;
+            let contents = BundleContents {
+                created_at: chrono::Utc::now(),
+                whitelist: storage.whitelist.entries.clone(),
+                policy,
+            };
+            let signed = SignedBundle::sign(&contents, &key);
+            std::fs::write(&out, serde_json::to_string_pretty(&signed)?)?;
+            println!(
+                "Bundle with {} whitelist entr{}{} written to {}",
+                contents.whitelist.len(),
+                if contents.whitelist.len() == 1 { "y" } else { "ies" },
+                if contents.policy.is_some() { " and a policy" } else { "" },
+                out
+            );
+        }
+        BundleCommand::Import { path } => {
+            let config = config::Config::load(args.common.config.as_deref())?;
+            let path = std::path::PathBuf::from(shellexpand::tilde(&path).into_owned());
+            let bundle = Bundle::load(&path, &config.bundles.trusted_signers)?;
+            let dir = std::path::PathBuf::from(shellexpand::tilde(&args.common.storage_file).into_owned())
+                .join(bundle::BUNDLES_DIR);
+            std::fs::create_dir_all(&dir)?;
+            let file_name = path.file_name().context("bundle path has no file name")?;
+            let target = dir.join(file_name).with_extension("json");
+            std::fs::copy(&path, &target)?;
+            println!("{}", format!("Imported bundle signed by {} to {:?}", utils::sanitize_line(&bundle.signer), target).green());
+        }
+        BundleCommand::Verify { path } => {
+            let config = config::Config::load(args.common.config.as_deref())?;
+            let bundle = Bundle::load(std::path::Path::new(&shellexpand::tilde(&path).into_owned()), &config.bundles.trusted_signers)?;
+            println!(
+                "{}",
+                format!("Signed by {} at {}", utils::sanitize_line(&bundle.signer), bundle.contents.created_at.format("%Y-%m-%d %H:%M")).green()
+            );
+            for entry in &bundle.contents.whitelist {
+                println!(
+                    "  {} {} {} {} (approved by {}: {})",
+                    utils::sanitize_line(&entry.server),
+                    utils::sanitize_line(&entry.r#type),
+                    utils::sanitize_line(&entry.name),
+                    utils::sanitize_line(&entry.fingerprint),
+                    utils::sanitize_line(&entry.approved_by),
+                    utils::sanitize_line(&entry.reason)
+                );
+            }
+            if let Some(policy) = &bundle.contents.policy {
+                println!("Policy:\n{}", utils::sanitize(policy));
+            }
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
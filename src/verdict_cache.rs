@@ -0,0 +1,176 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+
+use crate::mcp_types::Entity;
+use crate::utils::write_atomic;
+
+pub const CACHE_FILE: &str = "verdict_cache.json";
+pub const DEFAULT_CACHE_TTL_HOURS: i64 = 24 * 7;
+/// Bump when the layout of cached entries changes.
+const CACHE_VERSION: u32 = 1;
+
+/// Fingerprint of everything a verifier sees of an entity: name, description,
+/// input schema and annotations. Unlike the description hash kept by
+/// `StorageFile`, a changed schema also invalidates cached verdicts.
+pub fn fingerprint(entity: &Entity) -> String {
+    let data = serde_json::to_string(entity).unwrap_or_default();
+    format!("{:x}", md5::compute(data.as_bytes()))
+}
+
+/// Short stable version for a prompt, so editing a custom prompt invalidates its verdicts.
+pub fn prompt_version(version: &str, prompt: &str) -> String {
+    let hash = format!("{:x}", md5::compute(prompt.as_bytes()));
+    format!("{}-{}", version, &hash[..8])
+}
+
+/// Everything a cached verdict depends on.
+#[derive(Debug, Clone)]
+pub struct VerdictKey {
+    pub fingerprint: String,
+    /// Which verifier produced the verdict, e.g. `verification` or `llm`
+    pub verifier: &'static str,
+    /// Model name, or the endpoint for verifiers without one
+    pub model: String,
+    pub prompt_version: String,
+}
+
+impl VerdictKey {
+    fn as_string(&self) -> String {
+        format!("{}|{}|{}|{}", self.verifier, self.model, self.prompt_version, self.fingerprint)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CacheEntry {
+    verdict: serde_json::Value,
+    cached_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheData {
+    version: u32,
+    entries: HashMap<String, CacheEntry>,
+}
+
+/// Verdicts of the remote verifier and the LLM judge, stored next to the scan
+/// history so unchanged entities are not re-evaluated on every scan.
+#[derive(Debug)]
+pub struct VerdictCache {
+    /// `None` when caching is disabled.
+    path: Option<PathBuf>,
+    ttl: Duration,
+    /// Ignore what is cached and overwrite it with fresh verdicts.
+    refresh: bool,
+    data: CacheData,
+}
+
+impl VerdictCache {
+    /// Load the cache from the storage directory `storage_path`.
+    /// A missing or unreadable cache file starts an empty cache.
+    pub fn load(storage_path: &str, ttl_hours: i64) -> Self {
+        let path = PathBuf::from(shellexpand::tilde(storage_path).into_owned()).join(CACHE_FILE);
+        let data = fs::read_to_string(&path)
+            .ok()
+            .and_then(|data| serde_json::from_str::<CacheData>(&data).ok())
+            .filter(|data| data.version == CACHE_VERSION)
+            .unwrap_or_default();
+        Self {
+            path: Some(path),
+            ttl: Duration::hours(ttl_hours),
+            refresh: false,
+            data,
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self {
+            path: None,
+            ttl: Duration::zero(),
+            refresh: false,
+            data: CacheData::default(),
+        }
+    }
+
+    pub fn with_refresh(mut self, refresh: bool) -> Self {
+        self.refresh = refresh;
+        self
+    }
+
+    pub fn get<T: DeserializeOwned>(&self, key: &VerdictKey) -> Option<T> {
+        if self.path.is_none() || self.refresh {
+            return None;
+        }
+        let entry = self.data.entries.get(&key.as_string())?;
+        if Utc::now() - entry.cached_at > self.ttl {
+            return None;
+        }
+        serde_json::from_value(entry.verdict.clone()).ok()
+    }
+
+    pub fn put<T: Serialize>(&mut self, key: &VerdictKey, verdict: &T) {
+        if self.path.is_none() {
+            return;
+        }
+        if let Ok(verdict) = serde_json::to_value(verdict) {
+            self.data.entries.insert(key.as_string(), CacheEntry { verdict, cached_at: Utc::now() });
+        }
+    }
+
+    /// Write the cache, dropping expired entries.
+    pub fn save(&mut self) -> anyhow::Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let now = Utc::now();
+        let ttl = self.ttl;
+        self.data.entries.retain(|_, entry| now - entry.cached_at <= ttl);
+        self.data.version = CACHE_VERSION;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        write_atomic(path, serde_json::to_string_pretty(&self.data)?.as_bytes())
+            .with_context(|| format!("could not write verdict cache {:?}", path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(fingerprint: &str, model: &str) -> VerdictKey {
+        VerdictKey {
+            fingerprint: fingerprint.to_string(),
+            verifier: "llm",
+            model: model.to_string(),
+            prompt_version: "1".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cache_round_trip_and_invalidation() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("mcp-scan-cache-{}", std::process::id()));
+        let storage = dir.to_string_lossy().to_string();
+
+        let mut cache = VerdictCache::load(&storage, 1);
+        cache.put(&key("abc", "m1"), &"safe");
+        cache.save()?;
+
+        let cache = VerdictCache::load(&storage, 1);
+        assert_eq!(cache.get::<String>(&key("abc", "m1")).as_deref(), Some("safe"));
+        assert_eq!(cache.get::<String>(&key("abc", "m2")), None);
+        assert_eq!(cache.get::<String>(&key("changed", "m1")), None);
+        assert_eq!(cache.with_refresh(true).get::<String>(&key("abc", "m1")), None);
+
+        let expired = VerdictCache::load(&storage, 0);
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        assert_eq!(expired.get::<String>(&key("abc", "m1")), None);
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
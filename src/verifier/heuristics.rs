@@ -0,0 +1,25 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use crate::detectors::check_entity;
+use crate::mcp_types::Entity;
+use crate::verifier::{Assessment, ServerContext, Verifier, VerifierKind};
+
+/// The local detectors, run on every entity.
+pub struct HeuristicVerifier;
+
+#[async_trait]
+impl Verifier for HeuristicVerifier {
+    fn kind(&self) -> VerifierKind {
+        VerifierKind::Heuristics
+    }
+
+    async fn verify(&self, entities: &[Entity], _context: &ServerContext<'_>) -> anyhow::Result<HashMap<usize, Assessment>> {
+        Ok(entities
+            .iter()
+            .enumerate()
+            .map(|(i, entity)| (i, Assessment::checked(check_entity(entity))))
+            .collect())
+    }
+}
@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+
+use crate::llm::judge::{JUDGE_PROMPT_VERSION, LLMJudge};
+use crate::mcp_types::Entity;
+use crate::verdict_cache::prompt_version;
+use crate::verifier::{Assessment, ServerContext, Verifier, VerifierKind};
+
+/// Asks the LLM judge about each entity in turn.
+pub struct LLMVerifier {
+    judge: LLMJudge,
+    model: String,
+    prompt_version: String,
+}
+
+impl LLMVerifier {
+    pub fn new(judge: LLMJudge, model: &str, prompt: &str) -> Self {
+        Self {
+            judge,
+            model: model.to_string(),
+            prompt_version: prompt_version(JUDGE_PROMPT_VERSION, prompt),
+        }
+    }
+}
+
+#[async_trait]
+impl Verifier for LLMVerifier {
+    fn kind(&self) -> VerifierKind {
+        VerifierKind::Llm
+    }
+
+    fn cache_scope(&self) -> Option<(String, String)> {
+        Some((self.model.clone(), self.prompt_version.clone()))
+    }
+
+    async fn verify(&self, entities: &[Entity], _context: &ServerContext<'_>) -> anyhow::Result<HashMap<usize, Assessment>> {
+        let mut assessments = HashMap::new();
+        for (i, entity) in entities.iter().enumerate() {
+            let assessment = match self.judge.judge(entity).await {
+                Ok(verdict) => Assessment::checked(verdict.into_findings()),
+                Err(e) => Assessment::unverified(format!("LLM review failed: {:#}", e)),
+            };
+            assessments.insert(i, assessment);
+        }
+        Ok(assessments)
+    }
+}
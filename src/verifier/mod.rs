@@ -0,0 +1,159 @@
+pub mod heuristics;
+pub mod judge;
+pub mod policy;
+pub mod remote;
+
+use std::collections::HashMap;
+use std::fmt;
+
+use async_trait::async_trait;
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::mcp_types::{Entity, Finding, Server, Severity};
+
+/// Findings at or above this severity count as a verifier flagging the entity.
+pub const FLAG_SEVERITY: Severity = Severity::High;
+
+/// The verification backends that can be enabled with `--verifiers`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum VerifierKind {
+    /// The remote verification API at `--base-url`
+    Remote,
+    /// The local detectors
+    Heuristics,
+    /// The LLM judge
+    Llm,
+    /// Built-in rules on what a tool can do and how its server is run
+    Policy,
+}
+
+impl VerifierKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            VerifierKind::Remote => "remote",
+            VerifierKind::Heuristics => "heuristics",
+            VerifierKind::Llm => "llm",
+            VerifierKind::Policy => "policy",
+        }
+    }
+}
+
+impl fmt::Display for VerifierKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// How the votes of several verifiers are combined into one verdict per entity.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Fusion {
+    /// Flagged when any verifier flags it; unverified when none does but one gave no verdict
+    #[default]
+    Any,
+    /// Flagged when more than half of the verifiers that answered flag it
+    Majority,
+    /// Flagged when the weighted share of flagging verifiers reaches the threshold
+    Weighted,
+}
+
+impl Fusion {
+    /// `votes` holds each verifier's weight and whether it flagged the entity,
+    /// `None` when it gave no verdict. Returns whether the entity is flagged,
+    /// or `None` when the votes do not decide it, as when there are none.
+    pub fn decide(self, votes: &[(f64, Option<bool>)], threshold: f64) -> Option<bool> {
+        let answered = votes.iter().filter_map(|(w, v)| v.map(|v| (*w, v))).collect::<Vec<_>>();
+        match self {
+            Fusion::Any => {
+                if answered.iter().any(|(_, flagged)| *flagged) {
+                    Some(true)
+                } else if answered.is_empty() || answered.len() < votes.len() {
+                    None
+                } else {
+                    Some(false)
+                }
+            }
+            Fusion::Majority => {
+                let flagged = answered.iter().filter(|(_, flagged)| *flagged).count();
+                (!answered.is_empty()).then_some(flagged * 2 > answered.len())
+            }
+            Fusion::Weighted => {
+                let total: f64 = answered.iter().map(|(w, _)| w).sum();
+                let flagged: f64 = answered.iter().filter(|(_, flagged)| *flagged).map(|(w, _)| w).sum();
+                (total > 0.0).then(|| flagged / total >= threshold)
+            }
+        }
+    }
+}
+
+/// One verifier's answer for one entity.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "lowercase")]
+pub enum Assessment {
+    Checked { findings: Vec<Finding> },
+    Unverified { reason: String },
+}
+
+impl Assessment {
+    pub fn checked(findings: Vec<Finding>) -> Self {
+        Assessment::Checked { findings }
+    }
+
+    pub fn unverified(reason: impl Into<String>) -> Self {
+        Assessment::Unverified { reason: reason.into() }
+    }
+
+    /// Whether the verifier flagged the entity; `None` when it gave no verdict.
+    pub fn flagged(&self) -> Option<bool> {
+        match self {
+            Assessment::Checked { findings } => Some(findings.iter().any(|f| f.severity >= FLAG_SEVERITY)),
+            Assessment::Unverified { .. } => None,
+        }
+    }
+}
+
+/// The server the entities being verified come from.
+pub struct ServerContext<'a> {
+    pub name: &'a str,
+    pub server: &'a Server,
+}
+
+/// A source of verdicts on entities.
+#[async_trait]
+pub trait Verifier: Send + Sync {
+    fn kind(&self) -> VerifierKind;
+
+    /// Endpoint or model, and protocol or prompt version, under which
+    /// assessments are cached; `None` for verifiers cheaper to rerun than to cache.
+    fn cache_scope(&self) -> Option<(String, String)> {
+        None
+    }
+
+    /// Assess `entities`, keyed by position in `entities`; ids are not unique
+    /// when a server lists two entities with the same name. Entities missing
+    /// from the result are treated as unverified; an error marks all of them unverified.
+    async fn verify(&self, entities: &[Entity], context: &ServerContext<'_>) -> anyhow::Result<HashMap<usize, Assessment>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fusion() {
+        let votes = [(1.0, Some(true)), (1.0, Some(false)), (2.0, Some(false))];
+        assert_eq!(Fusion::Any.decide(&votes, 0.5), Some(true));
+        assert_eq!(Fusion::Majority.decide(&votes, 0.5), Some(false));
+        assert_eq!(Fusion::Weighted.decide(&votes, 0.5), Some(false));
+        assert_eq!(Fusion::Weighted.decide(&votes, 0.25), Some(true));
+
+        // 没有结论的验证器不参与多数和加权投票，但会让 any 无法判定通过
+        let votes = [(1.0, Some(false)), (1.0, None)];
+        assert_eq!(Fusion::Any.decide(&votes, 0.5), None);
+        assert_eq!(Fusion::Majority.decide(&votes, 0.5), Some(false));
+        assert_eq!(Fusion::Weighted.decide(&[(1.0, None)], 0.5), None);
+        assert_eq!(Fusion::Any.decide(&[], 0.5), None);
+    }
+}
@@ -0,0 +1,126 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::detectors::toxic_flow::{Capability, classify};
+use crate::mcp_types::{Entity, Finding, Server, Severity};
+use crate::policy::Policy;
+use crate::verifier::{Assessment, ServerContext, Verifier, VerifierKind};
+
+const DETECTOR: &str = "policy";
+
+/// Built-in rules on what a tool can do combined with how its server is run,
+/// plus the entity rules of the organisation policy when one is loaded.
+#[derive(Default)]
+pub struct PolicyVerifier {
+    policy: Option<Arc<Policy>>,
+}
+
+impl PolicyVerifier {
+    pub fn new(policy: Option<Arc<Policy>>) -> Self {
+        Self { policy }
+    }
+}
+
+/// Whether an SSE server is reached over plain HTTP on another machine.
+fn remote_plaintext(server: &Server) -> bool {
+    let Server::SSE(sse) = server else {
+        return false;
+    };
+    let Some(rest) = sse.url.strip_prefix("http://") else {
+        return false;
+    };
+    let host = rest.split(['/', '?']).next().unwrap_or_default();
+    let host = host.rsplit_once(':').map(|(h, _)| h).unwrap_or(host);
+    !matches!(host, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+fn check(entity: &Entity, server: &Server) -> Vec<Finding> {
+    let capabilities = classify(entity);
+    let mut findings = Vec::new();
+
+    let unpinned = match server {
+        Server::Stdio(stdio) => stdio.package().filter(|p| !p.is_pinned()),
+        Server::SSE(_) => None,
+    };
+    if let (Some(package), true) = (unpinned, capabilities.contains(&Capability::Exfiltration)) {
+        findings.push(
+            Finding::new(
+                DETECTOR,
+                Severity::High,
+                "tool can send data off the machine and its server package is not pinned to a version",
+            )
+            .with_evidence(format!("{} {}", package.runner, package.name)),
+        );
+    }
+
+    if capabilities.contains(&Capability::PrivateData) && remote_plaintext(server) {
+        findings.push(Finding::new(
+            DETECTOR,
+            Severity::High,
+            "tool reads private data from a remote server reached over plain HTTP",
+        ));
+    }
+
+    if capabilities.contains(&Capability::PrivateData) && capabilities.contains(&Capability::Exfiltration) {
+        findings.push(Finding::new(
+            DETECTOR,
+            Severity::Medium,
+            "tool can both read private data and send it off the machine",
+        ));
+    }
+    findings
+}
+
+#[async_trait]
+impl Verifier for PolicyVerifier {
+    fn kind(&self) -> VerifierKind {
+        VerifierKind::Policy
+    }
+
+    async fn verify(&self, entities: &[Entity], context: &ServerContext<'_>) -> anyhow::Result<HashMap<usize, Assessment>> {
+        Ok(entities
+            .iter()
+            .enumerate()
+            .map(|(i, entity)| {
+                let mut findings = check(entity, context.server);
+                if let Some(policy) = &self.policy {
+                    findings.extend(policy.check_entity(context.name, entity));
+                }
+                (i, Assessment::checked(findings))
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn server(config: serde_json::Value) -> Server {
+        serde_json::from_value(config).unwrap()
+    }
+
+    #[test]
+    fn test_rules() {
+        let tool = Entity::Tool(
+            serde_json::from_value(serde_json::json!({
+                "name": "send_email",
+                "description": "Send an email with the given body",
+                "inputSchema": { "type": "object" },
+            }))
+            .unwrap(),
+        );
+        let unpinned = server(serde_json::json!({ "command": "npx", "args": ["-y", "mail-mcp"] }));
+        let pinned = server(serde_json::json!({ "command": "npx", "args": ["-y", "mail-mcp@1.2.3"] }));
+
+        let findings = check(&tool, &unpinned);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].severity, Severity::High);
+        assert!(check(&tool, &pinned).is_empty());
+
+        assert!(remote_plaintext(&server(serde_json::json!({ "url": "http://mcp.example.com:8080/sse", "headers": {} }))));
+        assert!(!remote_plaintext(&server(serde_json::json!({ "url": "http://localhost:8080/sse", "headers": {} }))));
+    }
+}
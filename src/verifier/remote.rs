@@ -0,0 +1,44 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use crate::config::HttpSettings;
+use crate::mcp_types::Entity;
+use crate::verifier::{Assessment, ServerContext, Verifier, VerifierKind};
+use crate::verify_api::{VERIFY_API_VERSION, verify_server};
+
+/// The remote verification API. Its errors are `HttpError`s.
+pub struct RemoteVerifier {
+    base_url: String,
+    http: HttpSettings,
+    token_budget: usize,
+    /// How long to wait for the answer to one batch.
+    timeout: Duration,
+}
+
+impl RemoteVerifier {
+    pub fn new(base_url: &str, http: HttpSettings, token_budget: usize, timeout: Duration) -> Self {
+        Self {
+            base_url: base_url.to_string(),
+            http,
+            token_budget,
+            timeout,
+        }
+    }
+}
+
+#[async_trait]
+impl Verifier for RemoteVerifier {
+    fn kind(&self) -> VerifierKind {
+        VerifierKind::Remote
+    }
+
+    fn cache_scope(&self) -> Option<(String, String)> {
+        Some((self.base_url.clone(), VERIFY_API_VERSION.to_string()))
+    }
+
+    async fn verify(&self, entities: &[Entity], _context: &ServerContext<'_>) -> anyhow::Result<HashMap<usize, Assessment>> {
+        Ok(verify_server(entities, &self.base_url, &self.http, self.token_budget, self.timeout).await?)
+    }
+}
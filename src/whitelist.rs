@@ -0,0 +1,263 @@
+//! Approved entities, stored as `whitelist.json` in the storage directory.
+//!
+//! An entry approves one entity — identified by server, type and name — with
+//! one fingerprint of its name, description, input schema and annotations
+//! (see `verdict_cache::fingerprint`). The same entity on another server, or
+//! with a changed schema, is not approved by it.
+//!
+//! The first format was a flat `{"type.name": "hash"}` map that matched a
+//! description hash against every entity. [`Whitelist::migrate`] scopes those
+//! entries to the servers the hash was last seen on and marks them
+//! `description_only`: they approve nothing until the entity is approved again.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::mcp_types::{Entity, ScannedEntities, entity_type_to_str};
+
+pub const WHITELIST_VERSION: u32 = 2;
+
+/// Who approved a migrated entry.
+const MIGRATED_BY: &str = "migration";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WhitelistEntry {
+    pub server: String,
+    pub r#type: String,
+    pub name: String,
+    /// Fingerprint of the entity the approval was given for; the description
+    /// hash for `description_only` entries.
+    pub fingerprint: String,
+    pub approved_by: String,
+    pub approved_at: DateTime<Utc>,
+    pub reason: String,
+    /// The approval no longer applies after this time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Migrated from the unscoped whitelist, whose hash covered the description
+    /// only. Not an approval; the entity is shown for review again.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub description_only: bool,
+}
+
+impl WhitelistEntry {
+    /// Server, type and name, compared field by field: joined into one string,
+    /// server `a.tool` with tool `b` and server `a` with tool `tool.b` would collide.
+    pub fn key(&self) -> (&str, &str, &str) {
+        (&self.server, &self.r#type, &self.name)
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.expires_at.is_some_and(|expires| Utc::now() > expires)
+    }
+}
+
+/// The `(server, name)` readings of `key`, a `server.type.name` key of the
+/// scanned entities. There is more than one when the server or the name
+/// contains `.type.` itself.
+fn split_scanned_key<'a>(key: &'a str, entity_type: &str) -> Vec<(&'a str, &'a str)> {
+    let separator = format!(".{}.", entity_type);
+    key.match_indices(&separator)
+        .map(|(i, _)| (&key[..i], &key[i + separator.len()..]))
+        .collect()
+}
+
+/// End of the day `date` (`YYYY-MM-DD`), for `--expires`.
+pub fn parse_expiry(date: &str) -> Result<DateTime<Utc>, String> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map(|d| d.and_hms_opt(23, 59, 59).unwrap().and_utc())
+        .map_err(|e| format!("invalid date {:?}, expected YYYY-MM-DD: {}", date, e))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Whitelist {
+    pub version: u32,
+    pub entries: Vec<WhitelistEntry>,
+}
+
+impl Default for Whitelist {
+    fn default() -> Self {
+        Self { version: WHITELIST_VERSION, entries: Vec::new() }
+    }
+}
+
+impl Whitelist {
+    /// Parse `whitelist.json`, migrating the legacy format with the help of
+    /// the scanned entities. For a legacy file, also returns the legacy keys
+    /// that could not be scoped and were dropped.
+    pub fn parse(data: &str, scanned: &ScannedEntities) -> Result<(Self, Option<Vec<String>>), serde_json::Error> {
+        match serde_json::from_str::<Self>(data) {
+            Ok(whitelist) => Ok((whitelist, None)),
+            Err(e) => match serde_json::from_str::<BTreeMap<String, String>>(data) {
+                Ok(legacy) => {
+                    let (whitelist, dropped) = Self::migrate(&legacy, scanned);
+                    Ok((whitelist, Some(dropped)))
+                }
+                Err(_) => Err(e),
+            },
+        }
+    }
+
+    /// Turn legacy `type.name -> hash` entries into scoped entries, one per
+    /// server a matching entity with that hash was scanned on. Scanned keys
+    /// that can be split into server and name in more than one way are not
+    /// trusted. Entries never seen on any server are returned so they can be reported.
+    pub fn migrate(legacy: &BTreeMap<String, String>, scanned: &ScannedEntities) -> (Self, Vec<String>) {
+        let mut whitelist = Self::default();
+        let mut dropped = Vec::new();
+        let now = Utc::now();
+        for (legacy_key, hash) in legacy {
+            let (entity_type, name) = legacy_key.split_once('.').unwrap_or(("tool", legacy_key));
+            let mut servers: Vec<&str> = scanned
+                .iter()
+                .filter(|(_, e)| e.r#type == entity_type && &e.hash == hash)
+                .filter_map(|(k, _)| match split_scanned_key(k, entity_type).as_slice() {
+                    [(server, n)] if *n == name => Some(*server),
+                    _ => None,
+                })
+                .collect();
+            servers.sort();
+            if servers.is_empty() {
+                dropped.push(legacy_key.clone());
+                continue;
+            }
+            for server in servers {
+                whitelist.insert(WhitelistEntry {
+                    server: server.to_string(),
+                    r#type: entity_type.to_string(),
+                    name: name.to_string(),
+                    fingerprint: hash.clone(),
+                    approved_by: MIGRATED_BY.to_string(),
+                    approved_at: now,
+                    reason: "migrated from the unscoped whitelist".to_string(),
+                    expires_at: None,
+                    description_only: true,
+                });
+            }
+        }
+        (whitelist, dropped)
+    }
+
+    /// Add `entry`, replacing any earlier approval of the same entity.
+    pub fn insert(&mut self, entry: WhitelistEntry) {
+        self.entries.retain(|e| e.key() != entry.key());
+        self.entries.push(entry);
+        self.entries.sort_by(|a, b| a.key().cmp(&b.key()));
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The unexpired entry approving `entity` on `server` with `fingerprint`.
+    pub fn lookup(&self, server: &str, entity: &Entity, fingerprint: &str) -> Option<&WhitelistEntry> {
+        self.find(server, entity, fingerprint, false)
+    }
+
+    /// The unexpired migrated entry for `entity` on `server` with the description
+    /// hash `hash`, which needs to be approved again.
+    pub fn lookup_description_only(&self, server: &str, entity: &Entity, hash: &str) -> Option<&WhitelistEntry> {
+        self.find(server, entity, hash, true)
+    }
+
+    fn find(&self, server: &str, entity: &Entity, fingerprint: &str, description_only: bool) -> Option<&WhitelistEntry> {
+        let name = entity.name();
+        let key = (server, entity_type_to_str(entity), name.as_str());
+        self.entries.iter().find(|e| {
+            e.key() == key && e.fingerprint == fingerprint && e.description_only == description_only && !e.is_expired()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::mcp_types::ScannedEntity;
+
+    fn tool(name: &str) -> Entity {
+        Entity::Tool(
+            serde_json::from_value(serde_json::json!({
+                "name": name,
+                "description": "Adds two numbers",
+                "inputSchema": { "type": "object" },
+            }))
+            .unwrap(),
+        )
+    }
+
+    fn entry(server: &str, name: &str, expires_at: Option<DateTime<Utc>>) -> WhitelistEntry {
+        WhitelistEntry {
+            server: server.to_string(),
+            r#type: "tool".to_string(),
+            name: name.to_string(),
+            fingerprint: "abc".to_string(),
+            approved_by: "alice".to_string(),
+            approved_at: Utc::now(),
+            reason: "reviewed".to_string(),
+            expires_at,
+            description_only: false,
+        }
+    }
+
+    #[test]
+    fn test_lookup_is_scoped() {
+        let mut whitelist = Whitelist::default();
+        whitelist.insert(entry("math", "add", None));
+        whitelist.insert(entry("old", "add", Some(parse_expiry("2020-01-01").unwrap())));
+
+        assert!(whitelist.lookup("math", &tool("add"), "abc").is_some());
+        assert!(whitelist.lookup("math", &tool("add"), "other").is_none());
+        assert!(whitelist.lookup("math", &tool("sum"), "abc").is_none());
+        assert!(whitelist.lookup("evil", &tool("add"), "abc").is_none());
+        assert!(whitelist.lookup("old", &tool("add"), "abc").is_none());
+
+        whitelist.insert(entry("math", "add", None));
+        assert_eq!(whitelist.entries.len(), 2);
+        assert!(parse_expiry("next week").is_err());
+
+        // 服务器名和实体名中的点不会让两个实体共用一条批准
+        whitelist.insert(entry("a", "tool.b", None));
+        assert!(whitelist.lookup("a.tool", &tool("b"), "abc").is_none());
+        whitelist.insert(entry("a.tool", "b", None));
+        assert_eq!(whitelist.entries.len(), 4);
+    }
+
+    #[test]
+    fn test_migrate_legacy_whitelist() {
+        let keys = ["math.tool.add", "calc.v2.tool.add", "evil.tool.add", "a.tool.tool.b"];
+        let scanned: ScannedEntities = HashMap::from_iter(keys.map(|key| {
+            let hash = if key.starts_with("evil") { "zzz" } else { "abc" };
+            (
+                key.to_string(),
+                ScannedEntity {
+                    hash: hash.to_string(),
+                    r#type: "tool".to_string(),
+                    verified: true,
+                    timestamp: Utc::now(),
+                    description: None,
+                },
+            )
+        }));
+        let (whitelist, dropped) =
+            Whitelist::parse(r#"{"tool.add": "abc", "prompt.gone": "123", "tool.b": "abc"}"#, &scanned).unwrap();
+
+        let servers: Vec<_> = whitelist.entries.iter().map(|e| e.server.as_str()).collect();
+        assert_eq!(servers, ["calc.v2", "math"]);
+        // 旧条目只覆盖描述，迁移后不再放行
+        assert!(whitelist.entries.iter().all(|e| e.description_only));
+        assert!(whitelist.lookup("math", &tool("add"), "abc").is_none());
+        assert!(whitelist.lookup_description_only("math", &tool("add"), "abc").is_some());
+        // a.tool.tool.b 可以是 a.tool 的 b，也可以是 a 的 tool.b，不予迁移
+        assert_eq!(dropped.unwrap(), ["prompt.gone", "tool.b"]);
+
+        let data = serde_json::to_string(&whitelist).unwrap();
+        let (reloaded, dropped) = Whitelist::parse(&data, &scanned).unwrap();
+        assert_eq!(reloaded.entries, whitelist.entries);
+        assert!(dropped.is_none());
+        assert!(Whitelist::parse("[1, 2]", &scanned).is_err());
+    }
+}